@@ -0,0 +1,249 @@
+use std::fs;
+  use std::io;
+  use std::path::{Path, PathBuf};
+
+  use serde_json::{json, Map, Value};
+
+  use crate::config;
+  use crate::matcher;
+  use crate::store;
+  use crate::trust;
+
+  #[derive(Clone, Default, PartialEq, Eq, Debug)]
+  pub struct Binding {
+    pub enter: String,
+    pub leave: String,
+    pub inherit: bool,
+    pub extend: bool,
+  }
+
+  impl Binding {
+    pub fn is_empty(&self) -> bool {
+      self.enter.is_empty() && self.leave.is_empty()
+    }
+
+    pub fn content(&self) -> String {
+      let mut content = if self.leave.is_empty() {
+        self.enter.clone()
+      } else {
+        format!("{}\0{}", self.enter, self.leave)
+      };
+      if self.inherit {
+        content.push_str("\0inherit");
+      }
+      if self.extend {
+        content.push_str("\0extend");
+      }
+      content
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+      match value {
+        Value::String(enter) => Some(Binding { enter: enter.clone(), ..Binding::default() }),
+        Value::Object(fields) => {
+          let command = |name: &str| fields.get(name).and_then(Value::as_str).unwrap_or_default().to_string();
+          let flag = |name: &str| fields.get(name).and_then(Value::as_bool).unwrap_or_default();
+          Some(Binding {
+            enter: command("enter"),
+            leave: command("leave"),
+            inherit: flag("inherit"),
+            extend: flag("extend"),
+          })
+        }
+        _ => None,
+      }
+    }
+
+    fn to_json(&self) -> Value {
+      if self.leave.is_empty() && !self.inherit && !self.extend {
+        return Value::String(self.enter.clone());
+      }
+
+      let mut fields = Map::new();
+      fields.insert(String::from("enter"), json!(self.enter));
+      if !self.leave.is_empty() {
+        fields.insert(String::from("leave"), json!(self.leave));
+      }
+      if self.inherit {
+        fields.insert(String::from("inherit"), json!(true));
+      }
+      if self.extend {
+        fields.insert(String::from("extend"), json!(true));
+      }
+      Value::Object(fields)
+    }
+  }
+
+  #[derive(Clone, PartialEq, Eq, Debug)]
+  pub enum Pattern {
+    Glob(String),
+    Marker(String),
+  }
+
+  impl Pattern {
+    pub fn label(&self) -> String {
+      match self {
+        Pattern::Glob(glob) => format!("glob {}", glob),
+        Pattern::Marker(marker) => format!("marker {}", marker),
+      }
+    }
+
+    fn matches(&self, dir: &Path) -> bool {
+      match self {
+        Pattern::Glob(glob) => {
+          let glob = glob.strip_suffix('/').filter(|glob| !glob.is_empty()).unwrap_or(glob);
+          matcher::glob_match(&matcher::expand_home(glob), &dir.to_string_lossy())
+        }
+        Pattern::Marker(marker) => match marker.strip_suffix('/') {
+          Some(name) => dir.join(name).is_dir(),
+          None => dir.join(marker).exists(),
+        },
+      }
+    }
+  }
+
+  #[derive(Clone, PartialEq, Eq, Debug)]
+  pub struct Bound {
+    pub dir: PathBuf,
+    pub pattern: Option<Pattern>,
+    pub binding: Binding,
+  }
+
+  impl Bound {
+    pub fn trust_key(&self) -> String {
+      match &self.pattern {
+        Some(pattern) => pattern.label(),
+        None => key(&self.dir),
+      }
+    }
+  }
+
+  pub struct Jump {
+    pub leave: Vec<Bound>,
+    pub enter: Vec<Bound>,
+  }
+
+  fn get_scripts_file_path() -> io::Result<PathBuf> {
+    Ok(config::get_data_dir()?.join("scripts.json"))
+  }
+
+  pub fn key(dir: &Path) -> String {
+    dir.to_string_lossy().to_string()
+  }
+
+  fn read_bindings(scripts_file: &Path) -> io::Result<Map<String, Value>> {
+    let content = match fs::read_to_string(scripts_file) {
+      Ok(content) => content,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Map::new()),
+      Err(e) => return Err(e),
+    };
+
+    match serde_json::from_str(&content) {
+      Ok(Value::Object(map)) => Ok(map),
+      _ => Ok(Map::new()),
+    }
+  }
+
+  fn exact(bindings: &Map<String, Value>, dir: &Path) -> Option<Binding> {
+    bindings.get(&key(dir)).and_then(Binding::from_json).filter(|binding| !binding.is_empty())
+  }
+
+  fn resolve(bindings: &Map<String, Value>, dir: &Path) -> Option<Bound> {
+    if let Some(binding) = exact(bindings, dir) {
+      return Some(Bound { dir: dir.to_path_buf(), pattern: None, binding });
+    }
+
+    config::get().bindings.iter().find(|(pattern, _)| pattern.matches(dir)).map(|(pattern, binding)| Bound {
+      dir: dir.to_path_buf(),
+      pattern: Some(pattern.clone()),
+      binding: binding.clone(),
+    })
+  }
+
+  pub fn get(dir: &Path) -> io::Result<Option<Binding>> {
+    Ok(exact(&read_bindings(&get_scripts_file_path()?)?, dir))
+  }
+
+  fn effective_in(bindings: &Map<String, Value>, dir: &Path) -> Vec<Bound> {
+    let mut effective = Vec::new();
+    let mut current = dir;
+    loop {
+      if let Some(bound) = resolve(bindings, current)
+        && (current == dir || bound.binding.inherit)
+      {
+        let extend = bound.binding.extend;
+        effective.push(bound);
+        if !extend {
+          break;
+        }
+      }
+      match current.parent() {
+        Some(parent) if parent != current => current = parent,
+        _ => break,
+      }
+    }
+    effective
+  }
+
+  pub fn effective(dir: &Path) -> io::Result<Vec<Bound>> {
+    Ok(effective_in(&read_bindings(&get_scripts_file_path()?)?, dir))
+  }
+
+  pub fn shadowed(dir: &Path) -> io::Result<Option<Bound>> {
+    let mut bindings = read_bindings(&get_scripts_file_path()?)?;
+    bindings.remove(&key(dir));
+    Ok(effective_in(&bindings, dir).into_iter().next())
+  }
+
+  pub fn jump(from: &Path, to: &Path) -> io::Result<Jump> {
+    let bindings = read_bindings(&get_scripts_file_path()?)?;
+    let before = effective_in(&bindings, from);
+    let after = effective_in(&bindings, to);
+
+    let leave = from
+      .ancestors()
+      .filter_map(|dir| resolve(&bindings, dir))
+      .filter(|bound| {
+        if bound.binding.inherit {
+          before.contains(bound) && !after.contains(bound)
+        } else {
+          !to.starts_with(&bound.dir)
+        }
+      })
+      .filter(|bound| !bound.binding.leave.is_empty())
+      .collect();
+
+    let enter = after
+      .iter()
+      .rev()
+      .filter(|bound| !bound.binding.inherit || !before.contains(bound))
+      .filter(|bound| !bound.binding.enter.is_empty())
+      .cloned()
+      .collect();
+
+    Ok(Jump { leave, enter })
+  }
+
+  pub fn save(dir: &Path, binding: &Binding) -> io::Result<()> {
+    let scripts_file = get_scripts_file_path()?;
+    let dir_str = key(dir);
+
+    if binding.is_empty() {
+      trust::forget(&dir_str)?;
+    } else {
+      trust::set(&dir_str, &binding.content(), true)?;
+    }
+
+    store::with_lock(&scripts_file, || {
+      let mut bindings = read_bindings(&scripts_file)?;
+
+      if binding.is_empty() {
+        bindings.remove(&dir_str);
+      } else {
+        bindings.insert(dir_str, binding.to_json());
+      }
+
+      let formatted = serde_json::to_string_pretty(&Value::Object(bindings))?;
+      store::write_atomic(&scripts_file, formatted.as_bytes())
+    })
+  }
@@ -0,0 +1,166 @@
+use std::ffi::OsString;
+  use std::path::PathBuf;
+
+  use crate::shell::Shell;
+
+  pub const USAGE: &str = "Usage:
+    ff [--config <file>] <command>
+    ff                             Open the navigator
+    ff <keywords>...               Jump to the best match in history
+    ff query [--list] [--score] [--print0] <keywords>...
+                                   Print matching directories from history
+    ff pick [--files] [--multi] [--print0] [--history]
+                                   Print the selected path(s) to stdout
+    <command> | ff pick [--multi] [--print0] [--history]
+                                   Pick from lines read on stdin
+    ff add <path>...               Record a visit to each directory
+    ff bind <allow|deny> [<dir>]   Trust or block the command bound to a directory
+    ff bind show [<dir>]           Show the binding that applies to a directory
+    ff init <bash|zsh|fish> [--no-hook]
+                                   Print the shell integration
+
+  Configuration is read from $XDG_CONFIG_HOME/ff/config.toml, or from
+  $FF_HOME/config.toml when FF_HOME is set.";
+
+  pub enum Command {
+    Help,
+    Interactive,
+    Jump(Vec<String>),
+    Query {
+      list: bool,
+      score: bool,
+      print0: bool,
+      keywords: Vec<String>,
+    },
+    Pick {
+      files: bool,
+      multi: bool,
+      print0: bool,
+      history: bool,
+    },
+    Add(Vec<PathBuf>),
+    Bind {
+      action: BindAction,
+      dir: Option<PathBuf>,
+    },
+    Init {
+      shell: Shell,
+      hook: bool,
+    },
+  }
+
+  #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+  pub enum BindAction {
+    Allow,
+    Deny,
+    Show,
+  }
+
+  pub fn take_config_option(args: &mut Vec<OsString>) -> Result<Option<PathBuf>, String> {
+    let Some(first) = args.first().and_then(|arg| arg.to_str()).map(str::to_string) else {
+      return Ok(None);
+    };
+
+    if first == "--config" {
+      if args.len() < 2 {
+        return Err(String::from("--config expects a file"));
+      }
+      let path = PathBuf::from(args.remove(1));
+      args.remove(0);
+      Ok(Some(path))
+    } else if let Some(path) = first.strip_prefix("--config=") {
+      args.remove(0);
+      Ok(Some(PathBuf::from(path)))
+    } else {
+      Ok(None)
+    }
+  }
+
+  pub fn parse_args(raw_args: &[OsString]) -> Result<Command, String> {
+    let args: Vec<String> = raw_args
+      .iter()
+      .map(|arg| arg.to_string_lossy().into_owned())
+      .collect();
+    let Some(first) = args.first() else {
+      return Ok(Command::Interactive);
+    };
+
+    match first.as_str() {
+      "init" => {
+        let Some(shell) = args.get(1).and_then(|name| Shell::from_name(name)) else {
+          return Err(String::from("ff init expects one of: bash, zsh, fish"));
+        };
+        let mut hook = true;
+        for arg in &args[2..] {
+          match arg.as_str() {
+            "--no-hook" => hook = false,
+            option => return Err(format!("unknown option for ff init: {}", option)),
+          }
+        }
+        Ok(Command::Init { shell, hook })
+      }
+      "add" => {
+        let paths: Vec<PathBuf> = raw_args[1..]
+          .iter()
+          .filter(|arg| arg.as_os_str() != "--")
+          .map(PathBuf::from)
+          .collect();
+        if paths.is_empty() {
+          return Err(String::from("ff add expects at least one path"));
+        }
+        Ok(Command::Add(paths))
+      }
+      "bind" => {
+        let action = match args.get(1).map(String::as_str) {
+          Some("allow") => BindAction::Allow,
+          Some("deny") => BindAction::Deny,
+          Some("show") => BindAction::Show,
+          _ => return Err(String::from("ff bind expects allow, deny or show")),
+        };
+        match &raw_args[2..] {
+          [] => Ok(Command::Bind { action, dir: None }),
+          [dir] => Ok(Command::Bind { action, dir: Some(PathBuf::from(dir)) }),
+          _ => Err(String::from("ff bind takes at most one directory")),
+        }
+      }
+      "query" => {
+        let mut list = false;
+        let mut score = false;
+        let mut print0 = false;
+        let mut keywords = Vec::new();
+        let mut options_done = false;
+        for arg in &args[1..] {
+          match arg.as_str() {
+            "--" if !options_done => options_done = true,
+            "-l" | "--list" if !options_done => list = true,
+            "-s" | "--score" if !options_done => score = true,
+            "-0" | "--print0" if !options_done => print0 = true,
+            option if !options_done && option.starts_with('-') && option.len() > 1 => {
+              return Err(format!("unknown option for ff query: {}", option));
+            }
+            keyword => keywords.push(keyword.to_string()),
+          }
+        }
+        Ok(Command::Query { list, score, print0, keywords })
+      }
+      "pick" => {
+        let mut files = false;
+        let mut multi = false;
+        let mut print0 = false;
+        let mut history = false;
+        for arg in &args[1..] {
+          match arg.as_str() {
+            "-f" | "--files" => files = true,
+            "-m" | "--multi" => multi = true,
+            "-0" | "--print0" => print0 = true,
+            "--history" => history = true,
+            option => return Err(format!("unknown option for ff pick: {}", option)),
+          }
+        }
+        Ok(Command::Pick { files, multi, print0, history })
+      }
+      "-h" | "--help" => Ok(Command::Help),
+      "--" => Ok(Command::Jump(args[1..].to_vec())),
+      _ => Ok(Command::Jump(args)),
+    }
+  }
@@ -0,0 +1,408 @@
+use std::env;
+  use std::fs;
+  use std::io;
+  use std::path::{Path, PathBuf};
+  use std::str::FromStr;
+  use std::sync::OnceLock;
+
+  use ratatui::style::Color;
+  use toml::{Table, Value};
+
+  use crate::bindings::{Binding, Pattern};
+  use crate::keys::{Keymap, Mode};
+  use crate::matcher::MatchMode;
+
+  const DOTFILES: &[(&str, &str)] = &[(".ff_config", "history"), (".ff_scripts", "scripts.json")];
+
+  static CONFIG: OnceLock<Config> = OnceLock::new();
+
+  pub struct Config {
+    pub show_files: bool,
+    pub match_mode: MatchMode,
+    pub match_paths: bool,
+    pub show_preview: bool,
+    pub wrap: bool,
+    pub colors: Colors,
+    pub keymap: Keymap,
+    pub ranking: Ranking,
+    pub exclude: Vec<String>,
+    pub previewers: Vec<(String, String)>,
+    pub bindings: Vec<(Pattern, Binding)>,
+  }
+
+  impl Default for Config {
+    fn default() -> Self {
+      Config {
+        show_files: false,
+        match_mode: MatchMode::default(),
+        match_paths: false,
+        show_preview: false,
+        wrap: true,
+        colors: Colors::default(),
+        keymap: Keymap::default(),
+        ranking: Ranking::default(),
+        exclude: Vec::new(),
+        previewers: Vec::new(),
+        bindings: Vec::new(),
+      }
+    }
+  }
+
+  pub struct Colors {
+    pub directory: Color,
+    pub file: Color,
+    pub selection_fg: Color,
+    pub selection_bg: Color,
+    pub matched: Color,
+    pub mark: Color,
+    pub prompt: Color,
+    pub bind_prompt: Color,
+    pub help_heading: Color,
+    pub help_key: Color,
+    pub message: Color,
+    pub preview_label: Color,
+    pub preview_ascii: Color,
+  }
+
+  impl Default for Colors {
+    fn default() -> Self {
+      Colors {
+        directory: Color::Blue,
+        file: Color::White,
+        selection_fg: Color::Black,
+        selection_bg: Color::LightCyan,
+        matched: Color::Yellow,
+        mark: Color::Magenta,
+        prompt: Color::Yellow,
+        bind_prompt: Color::Green,
+        help_heading: Color::Green,
+        help_key: Color::Yellow,
+        message: Color::DarkGray,
+        preview_label: Color::Yellow,
+        preview_ascii: Color::Green,
+      }
+    }
+  }
+
+  pub struct Ranking {
+    pub hour_weight: u64,
+    pub day_weight: u64,
+    pub week_weight: u64,
+    pub older_weight: u64,
+    pub max_accesses: usize,
+    pub max_total_visits: u64,
+    pub name_bonus: i64,
+    pub jump_ratio: u64,
+  }
+
+  impl Default for Ranking {
+    fn default() -> Self {
+      Ranking {
+        hour_weight: 400,
+        day_weight: 200,
+        week_weight: 50,
+        older_weight: 25,
+        max_accesses: 10,
+        max_total_visits: 10_000,
+        name_bonus: 32,
+        jump_ratio: 2,
+      }
+    }
+  }
+
+  pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+  }
+
+  pub fn init(config_file: Option<PathBuf>) -> io::Result<()> {
+    let explicit = config_file.is_some();
+    let config_file = match config_file {
+      Some(path) => path,
+      None => get_config_file_path()?,
+    };
+
+    if !explicit && env::var_os("FF_HOME").is_none() {
+      migrate_dotfiles()?;
+    }
+
+    let config = if explicit || config_file.exists() {
+      let content = fs::read_to_string(&config_file)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", config_file.display(), e)))?;
+      parse(&content)
+        .map_err(|message| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", config_file.display(), message)))?
+    } else {
+      Config::default()
+    };
+
+    let _ = CONFIG.set(config);
+    Ok(())
+  }
+
+  fn get_home_dir() -> io::Result<PathBuf> {
+    match env::var("HOME") {
+      Ok(home) => Ok(PathBuf::from(home)),
+      Err(_) => Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "HOME environment variable not set"
+      )),
+    }
+  }
+
+  fn get_xdg_dir(variable: &str, fallback: &str) -> io::Result<PathBuf> {
+    match env::var_os(variable) {
+      Some(dir) if Path::new(&dir).is_absolute() => Ok(PathBuf::from(dir)),
+      _ => Ok(get_home_dir()?.join(fallback)),
+    }
+  }
+
+  pub fn get_config_file_path() -> io::Result<PathBuf> {
+    if let Some(ff_home) = env::var_os("FF_HOME") {
+      return Ok(PathBuf::from(ff_home).join("config.toml"));
+    }
+
+    Ok(get_xdg_dir("XDG_CONFIG_HOME", ".config")?.join("ff").join("config.toml"))
+  }
+
+  pub fn get_data_dir() -> io::Result<PathBuf> {
+    let data_dir = match env::var_os("FF_HOME") {
+      Some(ff_home) => PathBuf::from(ff_home),
+      None => get_xdg_dir("XDG_DATA_HOME", ".local/share")?.join("ff"),
+    };
+
+    fs::create_dir_all(&data_dir)?;
+    Ok(data_dir)
+  }
+
+  pub fn get_state_dir() -> io::Result<PathBuf> {
+    let state_dir = match env::var_os("FF_HOME") {
+      Some(ff_home) => PathBuf::from(ff_home),
+      None => get_xdg_dir("XDG_STATE_HOME", ".local/state")?.join("ff"),
+    };
+
+    fs::create_dir_all(&state_dir)?;
+    Ok(state_dir)
+  }
+
+  fn migrate_dotfiles() -> io::Result<()> {
+    let home_dir = get_home_dir()?;
+
+    for (dotfile, name) in DOTFILES {
+      let legacy_file = home_dir.join(dotfile);
+      if !legacy_file.exists() {
+        continue;
+      }
+      let target = get_data_dir()?.join(name);
+      if target.exists() {
+        continue;
+      }
+
+      match fs::rename(&legacy_file, &target) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+          fs::copy(&legacy_file, &target)?;
+          fs::remove_file(&legacy_file)?;
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        result => result?,
+      }
+    }
+
+    Ok(())
+  }
+
+  fn parse(content: &str) -> Result<Config, String> {
+    let table: Table = content.parse().map_err(|e: toml::de::Error| e.message().to_string())?;
+    let mut config = Config::default();
+
+    for (section, value) in &table {
+      if section == "bindings" {
+        config.bindings = parse_bindings(value)?;
+        continue;
+      }
+      let Value::Table(entries) = value else {
+        return Err(format!("'{}' must be a table", section));
+      };
+      match section.as_str() {
+        "general" => parse_general(&mut config, entries)?,
+        "colors" => parse_colors(&mut config.colors, entries)?,
+        "keys" => {
+          let mut overrides = Vec::new();
+          for (key, value) in entries {
+            let Value::Table(actions) = value else {
+              overrides.push((Mode::Normal, key.clone(), expect_strings("keys", key, value)?));
+              continue;
+            };
+            let Some(mode) = Mode::from_name(key) else {
+              return Err(format!("unknown section [keys.{}]", key));
+            };
+            for (action, sequences) in actions {
+              overrides.push((mode, action.clone(), expect_strings(&format!("keys.{}", key), action, sequences)?));
+            }
+          }
+          config.keymap = Keymap::new(&overrides)?;
+        }
+        "ranking" => parse_ranking(&mut config.ranking, entries)?,
+        "exclude" => {
+          for (key, value) in entries {
+            match key.as_str() {
+              "dirs" => config.exclude = expect_strings("exclude", key, value)?,
+              _ => return Err(unknown_key("exclude", key)),
+            }
+          }
+        }
+        "previewers" => {
+          for (key, value) in entries {
+            config.previewers.push((key.clone(), expect_str("previewers", key, value)?.to_string()));
+          }
+        }
+        _ => return Err(format!("unknown section [{}]", section)),
+      }
+    }
+
+    Ok(config)
+  }
+
+  fn parse_general(config: &mut Config, entries: &Table) -> Result<(), String> {
+    for (key, value) in entries {
+      match key.as_str() {
+        "view" => {
+          config.show_files = match expect_str("general", key, value)? {
+            "directories" => false,
+            "files" => true,
+            _ => return Err(invalid("general", key, "\"directories\" or \"files\"")),
+          }
+        }
+        "match" => {
+          config.match_mode = match expect_str("general", key, value)? {
+            "fuzzy" => MatchMode::Fuzzy,
+            "exact" => MatchMode::Exact,
+            _ => return Err(invalid("general", key, "\"fuzzy\" or \"exact\"")),
+          }
+        }
+        "match_paths" => config.match_paths = expect_bool("general", key, value)?,
+        "preview" => config.show_preview = expect_bool("general", key, value)?,
+        "wrap" => config.wrap = expect_bool("general", key, value)?,
+        _ => return Err(unknown_key("general", key)),
+      }
+    }
+    Ok(())
+  }
+
+  fn parse_colors(colors: &mut Colors, entries: &Table) -> Result<(), String> {
+    for (key, value) in entries {
+      let color = Color::from_str(expect_str("colors", key, value)?)
+        .map_err(|_| invalid("colors", key, "a color name, 0-255 or #rrggbb"))?;
+      let slot = match key.as_str() {
+        "directory" => &mut colors.directory,
+        "file" => &mut colors.file,
+        "selection_fg" => &mut colors.selection_fg,
+        "selection_bg" => &mut colors.selection_bg,
+        "match" => &mut colors.matched,
+        "mark" => &mut colors.mark,
+        "prompt" => &mut colors.prompt,
+        "bind_prompt" => &mut colors.bind_prompt,
+        "help_heading" => &mut colors.help_heading,
+        "help_key" => &mut colors.help_key,
+        "message" => &mut colors.message,
+        "preview_label" => &mut colors.preview_label,
+        "preview_ascii" => &mut colors.preview_ascii,
+        _ => return Err(unknown_key("colors", key)),
+      };
+      *slot = color;
+    }
+    Ok(())
+  }
+
+  fn parse_ranking(ranking: &mut Ranking, entries: &Table) -> Result<(), String> {
+    for (key, value) in entries {
+      let number = expect_u64("ranking", key, value)?;
+      match key.as_str() {
+        "hour" => ranking.hour_weight = number,
+        "day" => ranking.day_weight = number,
+        "week" => ranking.week_weight = number,
+        "older" => ranking.older_weight = number,
+        "max_accesses" if number > 0 => ranking.max_accesses = number as usize,
+        "max_total_visits" => ranking.max_total_visits = number,
+        "name_bonus" => ranking.name_bonus = number as i64,
+        "jump_ratio" if number > 0 => ranking.jump_ratio = number,
+        "max_accesses" | "jump_ratio" => return Err(invalid("ranking", key, "a positive integer")),
+        _ => return Err(unknown_key("ranking", key)),
+      }
+    }
+    Ok(())
+  }
+
+  fn parse_bindings(value: &Value) -> Result<Vec<(Pattern, Binding)>, String> {
+    let Value::Array(rules) = value else {
+      return Err(String::from("'bindings' must be a list of [[bindings]] tables"));
+    };
+
+    let mut bindings = Vec::new();
+    for rule in rules {
+      let Value::Table(entries) = rule else {
+        return Err(String::from("'bindings' must be a list of [[bindings]] tables"));
+      };
+      let mut pattern = None;
+      let mut binding = Binding::default();
+      for (key, value) in entries {
+        match key.as_str() {
+          "glob" | "marker" if pattern.is_some() => {
+            return Err(String::from("[[bindings]] takes either a glob or a marker, not both"));
+          }
+          "glob" => pattern = Some(Pattern::Glob(expect_str("bindings", key, value)?.to_string())),
+          "marker" => pattern = Some(Pattern::Marker(expect_str("bindings", key, value)?.to_string())),
+          "enter" => binding.enter = expect_str("bindings", key, value)?.to_string(),
+          "leave" => binding.leave = expect_str("bindings", key, value)?.to_string(),
+          "inherit" => binding.inherit = expect_bool("bindings", key, value)?,
+          "extend" => binding.extend = expect_bool("bindings", key, value)?,
+          _ => return Err(unknown_key("bindings", key)),
+        }
+      }
+      let Some(pattern) = pattern else {
+        return Err(String::from("[[bindings]] needs a glob or a marker"));
+      };
+      let (Pattern::Glob(text) | Pattern::Marker(text)) = &pattern;
+      if text.is_empty() {
+        return Err(String::from("[[bindings]] glob and marker must not be empty"));
+      }
+      if binding.is_empty() {
+        return Err(format!("[[bindings]] {} needs an enter or leave command", pattern.label()));
+      }
+      bindings.push((pattern, binding));
+    }
+    Ok(bindings)
+  }
+
+  fn unknown_key(section: &str, key: &str) -> String {
+    format!("unknown key '{}' in [{}]", key, section)
+  }
+
+  fn invalid(section: &str, key: &str, expected: &str) -> String {
+    format!("[{}] {} must be {}", section, key, expected)
+  }
+
+  fn expect_str<'a>(section: &str, key: &str, value: &'a Value) -> Result<&'a str, String> {
+    value.as_str().ok_or_else(|| invalid(section, key, "a string"))
+  }
+
+  fn expect_bool(section: &str, key: &str, value: &Value) -> Result<bool, String> {
+    value.as_bool().ok_or_else(|| invalid(section, key, "true or false"))
+  }
+
+  fn expect_u64(section: &str, key: &str, value: &Value) -> Result<u64, String> {
+    value
+      .as_integer()
+      .and_then(|number| u64::try_from(number).ok())
+      .ok_or_else(|| invalid(section, key, "a non-negative integer"))
+  }
+
+  fn expect_strings(section: &str, key: &str, value: &Value) -> Result<Vec<String>, String> {
+    match value {
+      Value::String(text) => Ok(vec![text.clone()]),
+      Value::Array(items) => items
+        .iter()
+        .map(|item| item.as_str().map(str::to_string))
+        .collect::<Option<Vec<String>>>()
+        .ok_or_else(|| invalid(section, key, "a string or a list of strings")),
+      _ => Err(invalid(section, key, "a string or a list of strings")),
+    }
+  }
@@ -0,0 +1,249 @@
+use unicode_segmentation::UnicodeSegmentation;
+  use unicode_width::UnicodeWidthStr;
+
+  use crate::keys::Action;
+
+  #[derive(Default)]
+  pub struct LineEditor {
+    text: String,
+    cursor: usize,
+    scroll: usize,
+  }
+
+  impl LineEditor {
+    pub fn new(text: String) -> Self {
+      let cursor = text.len();
+      LineEditor { text, cursor, scroll: 0 }
+    }
+
+    pub fn text(&self) -> &str {
+      &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+      self.text.is_empty()
+    }
+
+    pub fn handle(&mut self, action: Option<Action>, typed: Option<char>) -> bool {
+      match action {
+        Some(Action::CursorLeft) => self.move_to(self.previous_boundary()),
+        Some(Action::CursorRight) => self.move_to(self.next_boundary()),
+        Some(Action::LineStart) => self.move_to(0),
+        Some(Action::LineEnd) => self.move_to(self.text.len()),
+        Some(Action::WordLeft) => self.move_to(self.word_start(is_alphanumeric)),
+        Some(Action::WordRight) => self.move_to(self.word_end(is_alphanumeric)),
+        Some(Action::DeleteChar) => self.delete(self.previous_boundary(), self.cursor),
+        Some(Action::DeleteNextChar) => self.delete(self.cursor, self.next_boundary()),
+        Some(Action::DeleteWord) => self.delete(self.word_start(is_not_whitespace), self.cursor),
+        Some(Action::DeleteToStart) => self.delete(0, self.cursor),
+        Some(Action::DeleteToEnd) => self.delete(self.cursor, self.text.len()),
+        _ => match typed {
+          Some(c) => self.insert(c.encode_utf8(&mut [0; 4])),
+          None => false,
+        },
+      }
+    }
+
+    pub fn paste(&mut self, text: &str) -> bool {
+      let text: String = text
+        .trim_end_matches(['\r', '\n'])
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+      self.insert(&text)
+    }
+
+    pub fn view(&mut self, width: usize) -> (&str, usize) {
+      let width = width.max(1);
+      let total = self.text.width();
+      let cursor = self.text[..self.cursor].width();
+
+      self.scroll = self.scroll.min((total + 1).saturating_sub(width));
+      if cursor < self.scroll {
+        self.scroll = cursor;
+      } else if cursor >= self.scroll + width {
+        self.scroll = cursor + 1 - width;
+      }
+
+      let mut start = None;
+      let mut end = self.text.len();
+      let mut column = 0;
+      for (index, grapheme) in self.text.grapheme_indices(true) {
+        if start.is_none() && column >= self.scroll {
+          start = Some((index, column));
+        }
+        column += grapheme.width();
+        if let Some((_, start_column)) = start
+          && column > start_column + width
+        {
+          end = index;
+          break;
+        }
+      }
+
+      let (start, start_column) = start.unwrap_or((self.text.len(), total));
+      (&self.text[start..end], cursor - start_column)
+    }
+
+    fn insert(&mut self, text: &str) -> bool {
+      self.text.insert_str(self.cursor, text);
+      self.cursor += text.len();
+      !text.is_empty()
+    }
+
+    fn delete(&mut self, start: usize, end: usize) -> bool {
+      self.text.replace_range(start..end, "");
+      self.cursor = start;
+      start < end
+    }
+
+    fn move_to(&mut self, cursor: usize) -> bool {
+      self.cursor = cursor;
+      false
+    }
+
+    fn previous_boundary(&self) -> usize {
+      self.text[..self.cursor].grapheme_indices(true).next_back().map_or(0, |(index, _)| index)
+    }
+
+    fn next_boundary(&self) -> usize {
+      self.text[self.cursor..].graphemes(true).next().map_or(self.cursor, |grapheme| self.cursor + grapheme.len())
+    }
+
+    fn word_start(&self, is_word: fn(&str) -> bool) -> usize {
+      let mut start = self.cursor;
+      let mut in_word = false;
+      for (index, grapheme) in self.text[..self.cursor].grapheme_indices(true).rev() {
+        if is_word(grapheme) {
+          in_word = true;
+        } else if in_word {
+          break;
+        }
+        start = index;
+      }
+      start
+    }
+
+    fn word_end(&self, is_word: fn(&str) -> bool) -> usize {
+      let mut in_word = false;
+      for (index, grapheme) in self.text[self.cursor..].grapheme_indices(true) {
+        if is_word(grapheme) {
+          in_word = true;
+        } else if in_word {
+          return self.cursor + index;
+        }
+      }
+      self.text.len()
+    }
+  }
+
+  fn is_alphanumeric(grapheme: &str) -> bool {
+    grapheme.chars().next().is_some_and(char::is_alphanumeric)
+  }
+
+  fn is_not_whitespace(grapheme: &str) -> bool {
+    !grapheme.chars().all(char::is_whitespace)
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+
+    fn apply(editor: &mut LineEditor, actions: &[Action]) {
+      for action in actions {
+        editor.handle(Some(*action), None);
+      }
+    }
+
+    #[test]
+    fn deletion_removes_whole_graphemes() {
+      let mut editor = LineEditor::new("cafe\u{301}".to_string());
+      assert!(editor.handle(Some(Action::DeleteChar), None));
+      assert_eq!(editor.text(), "caf");
+
+      let mut editor = LineEditor::new("e\u{301}x".to_string());
+      apply(&mut editor, &[Action::LineStart, Action::DeleteNextChar]);
+      assert_eq!(editor.text(), "x");
+
+      let mut editor = LineEditor::new("日本語".to_string());
+      apply(&mut editor, &[Action::CursorLeft, Action::DeleteChar]);
+      assert_eq!(editor.text(), "日語");
+      assert_eq!(editor.cursor, "日".len());
+
+      let mut editor = LineEditor::new(String::new());
+      assert!(!editor.handle(Some(Action::DeleteChar), None));
+      assert!(!editor.handle(Some(Action::DeleteNextChar), None));
+    }
+
+    #[test]
+    fn word_motions_stop_at_word_boundaries() {
+      let mut editor = LineEditor::new("foo bar-baz  qux".to_string());
+      apply(&mut editor, &[Action::WordLeft]);
+      assert_eq!(editor.cursor, 13);
+      apply(&mut editor, &[Action::WordLeft]);
+      assert_eq!(editor.cursor, 8);
+      apply(&mut editor, &[Action::WordLeft]);
+      assert_eq!(editor.cursor, 4);
+
+      apply(&mut editor, &[Action::LineStart, Action::WordRight]);
+      assert_eq!(editor.cursor, 3);
+      apply(&mut editor, &[Action::WordRight]);
+      assert_eq!(editor.cursor, 7);
+      apply(&mut editor, &[Action::WordRight, Action::WordRight, Action::WordRight]);
+      assert_eq!(editor.cursor, editor.text().len());
+    }
+
+    #[test]
+    fn word_deletion_takes_everything_back_to_whitespace() {
+      let mut editor = LineEditor::new("cd ~/src/ff-rs ".to_string());
+      assert!(editor.handle(Some(Action::DeleteWord), None));
+      assert_eq!(editor.text(), "cd ");
+      apply(&mut editor, &[Action::DeleteWord]);
+      assert_eq!(editor.text(), "");
+
+      let mut editor = LineEditor::new("one two three".to_string());
+      apply(&mut editor, &[Action::WordLeft, Action::DeleteToEnd]);
+      assert_eq!(editor.text(), "one two ");
+      apply(&mut editor, &[Action::WordLeft, Action::DeleteToStart]);
+      assert_eq!(editor.text(), "two ");
+      assert_eq!(editor.cursor, 0);
+    }
+
+    #[test]
+    fn paste_replaces_control_characters() {
+      let mut editor = LineEditor::new("ab".to_string());
+      apply(&mut editor, &[Action::CursorLeft]);
+      assert!(editor.paste("x\ty\u{1b}[2Jz\r\n"));
+      assert_eq!(editor.text(), "ax y [2Jzb");
+      assert_eq!(editor.cursor, "ax y [2Jz".len());
+      assert!(!editor.paste("\n"));
+      assert_eq!(editor.text(), "ax y [2Jzb");
+    }
+
+    #[test]
+    fn view_scrolls_to_keep_the_cursor_visible() {
+      let mut editor = LineEditor::new("abcdefghij".to_string());
+      assert_eq!(editor.view(4), ("hij", 3));
+      apply(&mut editor, &[Action::CursorLeft, Action::CursorLeft, Action::CursorLeft]);
+      assert_eq!(editor.view(4), ("hij", 0));
+      apply(&mut editor, &[Action::CursorLeft]);
+      assert_eq!(editor.view(4), ("ghij", 0));
+      apply(&mut editor, &[Action::LineStart]);
+      assert_eq!(editor.view(4), ("abcd", 0));
+      apply(&mut editor, &[Action::CursorRight, Action::CursorRight, Action::CursorRight]);
+      assert_eq!(editor.view(4), ("abcd", 3));
+      apply(&mut editor, &[Action::CursorRight]);
+      assert_eq!(editor.view(4), ("bcde", 3));
+      assert_eq!(editor.view(20), ("abcdefghij", 4));
+    }
+
+    #[test]
+    fn view_measures_wide_characters_in_columns() {
+      let mut editor = LineEditor::new("日本語です".to_string());
+      assert_eq!(editor.view(5), ("です", 4));
+      apply(&mut editor, &[Action::CursorLeft]);
+      assert_eq!(editor.view(5), ("です", 2));
+      apply(&mut editor, &[Action::LineStart]);
+      assert_eq!(editor.view(5), ("日本", 0));
+    }
+  }
@@ -0,0 +1,36 @@
+use crate::keys::{Keymap, Mode};
+  use crate::matcher::{MatchMode, Query};
+
+  pub enum HelpItem {
+    Blank,
+    Heading(&'static str),
+    Entry(String, &'static str),
+  }
+
+  pub fn help_items(keymap: &Keymap, context: Mode, filter: &str) -> Vec<HelpItem> {
+    let modes = match context {
+      Mode::Normal | Mode::Help => Mode::ALL.to_vec(),
+      mode => vec![mode, Mode::Help],
+    };
+    let query = Query::parse(filter, MatchMode::Fuzzy);
+
+    let mut items = Vec::new();
+    for mode in modes {
+      let entries: Vec<(String, &'static str)> = keymap
+        .help(mode)
+        .into_iter()
+        .filter(|(keys, description)| query.matches(&format!("{} {}", keys, description)).is_some())
+        .collect();
+      if entries.is_empty() {
+        continue;
+      }
+
+      if !items.is_empty() {
+        items.push(HelpItem::Blank);
+      }
+      items.push(HelpItem::Heading(mode.title()));
+      items.extend(entries.into_iter().map(|(keys, description)| HelpItem::Entry(keys, description)));
+    }
+
+    items
+  }
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+  use std::env;
+  use std::ffi::OsString;
+  use std::fs::{self, File};
+  use std::io;
+  use std::os::unix::ffi::OsStringExt;
+  use std::path::{Path, PathBuf};
+  use std::time::{SystemTime, UNIX_EPOCH};
+
+  use crate::config;
+  use crate::matcher;
+  use crate::store;
+
+  const FORMAT_HEADER: &str = "# ff history v2";
+
+  const HOUR: u64 = 60 * 60;
+  const DAY: u64 = 24 * HOUR;
+  const WEEK: u64 = 7 * DAY;
+
+  pub type History = HashMap<PathBuf, HistoryEntry>;
+
+  #[derive(Clone, Debug, Default)]
+  pub struct HistoryEntry {
+    pub visits: u64,
+    pub accesses: Vec<u64>,
+  }
+
+  impl HistoryEntry {
+    pub fn single(timestamp: u64) -> Self {
+      HistoryEntry {
+        visits: 1,
+        accesses: vec![timestamp],
+      }
+    }
+
+    fn record(&mut self, timestamp: u64) {
+      self.visits += 1;
+      self.accesses.push(timestamp);
+      let max_accesses = config::get().ranking.max_accesses;
+      if self.accesses.len() > max_accesses {
+        let excess = self.accesses.len() - max_accesses;
+        self.accesses.drain(..excess);
+      }
+    }
+
+    pub fn frecency(&self, now: u64) -> u64 {
+      if self.accesses.is_empty() {
+        return 0;
+      }
+      let weight: u64 = self.accesses
+        .iter()
+        .map(|&timestamp| access_weight(now.saturating_sub(timestamp)))
+        .sum();
+      self.visits * weight / self.accesses.len() as u64
+    }
+  }
+
+  fn access_weight(age: u64) -> u64 {
+    let ranking = &config::get().ranking;
+    if age < HOUR {
+      ranking.hour_weight
+    } else if age < DAY {
+      ranking.day_weight
+    } else if age < WEEK {
+      ranking.week_weight
+    } else {
+      ranking.older_weight
+    }
+  }
+
+  pub fn now() -> u64 {
+    SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .unwrap()
+      .as_secs()
+  }
+
+  pub fn get_history_file_path() -> io::Result<PathBuf> {
+    let history_file = config::get_data_dir()?.join("history");
+
+    if !history_file.exists() {
+      File::create(&history_file)?;
+    }
+
+    Ok(history_file)
+  }
+
+  fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for chunk in path.as_os_str().as_encoded_bytes().utf8_chunks() {
+      for c in chunk.valid().chars() {
+        match c {
+          '%' | '|' | '\n' | '\r' => encoded.push_str(&format!("%{:02X}", c as u32)),
+          _ => encoded.push(c),
+        }
+      }
+      for byte in chunk.invalid() {
+        encoded.push_str(&format!("%{:02X}", byte));
+      }
+    }
+    encoded
+  }
+
+  fn decode_path(encoded: &str) -> Option<PathBuf> {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+      if bytes[index] == b'%' {
+        let hex = encoded.get(index + 1..index + 3)?;
+        decoded.push(u8::from_str_radix(hex, 16).ok()?);
+        index += 3;
+      } else {
+        decoded.push(bytes[index]);
+        index += 1;
+      }
+    }
+    Some(PathBuf::from(OsString::from_vec(decoded)))
+  }
+
+  fn parse_accesses(field: &str) -> Vec<u64> {
+    field
+      .split(',')
+      .filter_map(|timestamp| timestamp.parse().ok())
+      .collect()
+  }
+
+  fn parse_line(line: &str) -> Option<(PathBuf, HistoryEntry)> {
+    let mut fields = line.rsplitn(3, '|');
+    let accesses = parse_accesses(fields.next()?);
+    let visits: u64 = fields.next()?.parse().ok()?;
+    let path = decode_path(fields.next()?)?;
+    Some((path, HistoryEntry { visits, accesses }))
+  }
+
+  fn parse_legacy_line(line: &str) -> Option<(PathBuf, HistoryEntry)> {
+    let parts: Vec<&str> = line.split('|').collect();
+    let [path, timestamp] = parts[..] else {
+      return None;
+    };
+    let timestamp: u64 = timestamp.parse().unwrap_or(0);
+    Some((PathBuf::from(path), HistoryEntry::single(timestamp)))
+  }
+
+  pub fn read_history(history_file: &Path) -> io::Result<History> {
+    let mut history = HashMap::new();
+
+    if history_file.exists() {
+      let contents = fs::read(history_file)?;
+      let mut lines = contents.split(|&byte| byte == b'\n').peekable();
+      let versioned = lines.next_if(|line| *line == FORMAT_HEADER.as_bytes()).is_some();
+
+      for line in lines {
+        let line = String::from_utf8_lossy(line);
+        let parsed = if versioned {
+          parse_line(&line)
+        } else {
+          parse_legacy_line(&line)
+        };
+        if let Some((path, entry)) = parsed {
+          history.insert(path, entry);
+        }
+      }
+    }
+
+    Ok(history)
+  }
+
+  pub fn child_scores(history: &History, dir: &Path, now: u64) -> HashMap<PathBuf, u64> {
+    let mut scores = HashMap::new();
+    for (path, entry) in history {
+      let Some(child) = path.strip_prefix(dir).ok().and_then(|rest| rest.components().next()) else {
+        continue;
+      };
+      let score = scores.entry(dir.join(child)).or_insert(0);
+      *score = (*score).max(entry.frecency(now));
+    }
+    scores
+  }
+
+  fn age_history(history: &mut History) {
+    let total: u64 = history.values().map(|entry| entry.visits).sum();
+    if total <= config::get().ranking.max_total_visits {
+      return;
+    }
+    for entry in history.values_mut() {
+      entry.visits = entry.visits * 9 / 10;
+    }
+    history.retain(|_, entry| entry.visits > 0);
+  }
+
+  fn is_excluded(path: &Path) -> bool {
+    let mut patterns = config::get().exclude.clone();
+    if let Some(from_env) = env::var_os("FF_EXCLUDE_DIRS") {
+      patterns.extend(env::split_paths(&from_env).map(|pattern| pattern.to_string_lossy().to_string()));
+    }
+    let path = path.to_string_lossy();
+
+    patterns.iter().any(|pattern| matcher::glob_match(&matcher::expand_home(pattern), &path))
+  }
+
+  pub fn update_history(history_file: &Path, selected_dir: &Path) -> io::Result<()> {
+    if is_excluded(selected_dir) {
+      return Ok(());
+    }
+
+    store::with_lock(history_file, || {
+      let mut history = read_history(history_file)?;
+      let now = now();
+
+      history.entry(selected_dir.to_path_buf()).or_default().record(now);
+
+      age_history(&mut history);
+
+      let mut contents = format!("{}\n", FORMAT_HEADER);
+      for (path, entry) in &history {
+        let accesses: Vec<String> = entry.accesses.iter().map(u64::to_string).collect();
+        contents.push_str(&format!("{}|{}|{}\n", encode_path(path), entry.visits, accesses.join(",")));
+      }
+
+      store::write_atomic(history_file, contents.as_bytes())
+    })
+  }
@@ -0,0 +1,498 @@
+use std::collections::HashMap;
+
+  use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+  #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+  pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+  }
+
+  impl Key {
+    pub fn parse(text: &str) -> Option<Self> {
+      let mut rest = text;
+      let mut modifiers = KeyModifiers::NONE;
+
+      loop {
+        let lower = rest.to_lowercase();
+        let prefix = [
+          ("ctrl", KeyModifiers::CONTROL),
+          ("alt", KeyModifiers::ALT),
+          ("shift", KeyModifiers::SHIFT),
+        ]
+        .into_iter()
+        .find(|(name, _)| {
+          lower.len() > name.len() + 1
+            && lower.starts_with(name)
+            && matches!(lower.as_bytes()[name.len()], b'-' | b'+')
+        });
+        let Some((name, modifier)) = prefix else {
+          break;
+        };
+        modifiers |= modifier;
+        rest = &rest[name.len() + 1..];
+      }
+
+      let code = match rest.to_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        name if name.len() > 1 && name.starts_with('f') => KeyCode::F(name[1..].parse().ok().filter(|n| (1..=12).contains(n))?),
+        _ => {
+          let mut chars = rest.chars();
+          let c = chars.next()?;
+          if chars.next().is_some() {
+            return None;
+          }
+          if modifiers.contains(KeyModifiers::SHIFT) {
+            let mut upper = c.to_uppercase();
+            match (upper.next(), upper.next()) {
+              (Some(upper), None) if c.is_alphabetic() => KeyCode::Char(upper),
+              _ => return None,
+            }
+          } else {
+            KeyCode::Char(c)
+          }
+        }
+      };
+
+      Some(Key::new(code, modifiers))
+    }
+
+    pub fn parse_sequence(text: &str) -> Option<Vec<Self>> {
+      let keys: Vec<Key> = text.split_whitespace().map(Key::parse).collect::<Option<_>>()?;
+      if keys.is_empty() {
+        return None;
+      }
+      Some(keys)
+    }
+
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+      let code = match code {
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => KeyCode::Char(c.to_lowercase().next().unwrap_or(c)),
+        code => code,
+      };
+      let modifiers = match code {
+        KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+        _ => modifiers,
+      };
+      Key { code, modifiers }
+    }
+
+    pub fn from_event(event: &KeyEvent) -> Self {
+      Key::new(event.code, event.modifiers)
+    }
+
+    pub fn typed(&self) -> Option<char> {
+      match self.code {
+        KeyCode::Char(c) if !self.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => Some(c),
+        _ => None,
+      }
+    }
+
+    pub fn label(&self) -> String {
+      let mut label = String::new();
+      if self.modifiers.contains(KeyModifiers::CONTROL) {
+        label.push_str("Ctrl+");
+      }
+      if self.modifiers.contains(KeyModifiers::ALT) {
+        label.push_str("Alt+");
+      }
+      if self.modifiers.contains(KeyModifiers::SHIFT) {
+        label.push_str("Shift+");
+      }
+
+      match self.code {
+        KeyCode::Char(' ') => label.push_str("Space"),
+        KeyCode::Char(c) => label.push(c),
+        KeyCode::Up => label.push('↑'),
+        KeyCode::Down => label.push('↓'),
+        KeyCode::Left => label.push('←'),
+        KeyCode::Right => label.push('→'),
+        KeyCode::BackTab => label.push_str("Shift+Tab"),
+        KeyCode::F(n) => label.push_str(&format!("F{}", n)),
+        code => label.push_str(&format!("{:?}", code)),
+      }
+      label
+    }
+  }
+
+  fn sequence_label(keys: &[Key]) -> String {
+    keys.iter().map(Key::label).collect::<Vec<_>>().join(" ")
+  }
+
+  #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+  pub enum Mode {
+    Normal,
+    Search,
+    Bind,
+    Help,
+    Trust,
+  }
+
+  impl Mode {
+    pub const ALL: [Mode; 5] = [Mode::Normal, Mode::Search, Mode::Bind, Mode::Help, Mode::Trust];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+      Mode::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+      match self {
+        Mode::Normal => "normal",
+        Mode::Search => "search",
+        Mode::Bind => "bind",
+        Mode::Help => "help",
+        Mode::Trust => "trust",
+      }
+    }
+
+    pub fn title(self) -> &'static str {
+      match self {
+        Mode::Normal => "Keyboard Controls",
+        Mode::Search => "While searching",
+        Mode::Bind => "While binding a command",
+        Mode::Help => "In this help screen",
+        Mode::Trust => "When a bound command needs approval",
+      }
+    }
+  }
+
+  #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+  pub enum Action {
+    Down,
+    Up,
+    Open,
+    EnterDir,
+    Parent,
+    Search,
+    Jump,
+    ToggleFiles,
+    Preview,
+    PreviewDown,
+    PreviewUp,
+    Bind,
+    Mark,
+    Help,
+    Quit,
+    Cancel,
+    Accept,
+    DeleteChar,
+    ToggleMatchMode,
+    ToggleMatchPaths,
+    Filter,
+    Top,
+    Bottom,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    ScreenTop,
+    ScreenMiddle,
+    ScreenBottom,
+    CursorLeft,
+    CursorRight,
+    LineStart,
+    LineEnd,
+    WordLeft,
+    WordRight,
+    DeleteNextChar,
+    DeleteWord,
+    DeleteToStart,
+    DeleteToEnd,
+    Allow,
+    Deny,
+    Skip,
+    SwitchField,
+    ToggleInherit,
+    ToggleExtend,
+  }
+
+  const ACTIONS: &[(Mode, &str, Action, &[&str], &str)] = &[
+    (Mode::Normal, "up", Action::Up, &["k", "up"], "Move selection up"),
+    (Mode::Normal, "down", Action::Down, &["j", "down"], "Move selection down"),
+    (Mode::Normal, "top", Action::Top, &["g g", "home"], "Go to the first entry (or entry N with a count)"),
+    (Mode::Normal, "bottom", Action::Bottom, &["G", "end"], "Go to the last entry (or entry N with a count)"),
+    (Mode::Normal, "half_page_down", Action::HalfPageDown, &["ctrl-d"], "Move down half a page"),
+    (Mode::Normal, "half_page_up", Action::HalfPageUp, &["ctrl-u"], "Move up half a page"),
+    (Mode::Normal, "page_down", Action::PageDown, &["pagedown"], "Move down a page"),
+    (Mode::Normal, "page_up", Action::PageUp, &["pageup"], "Move up a page"),
+    (Mode::Normal, "screen_top", Action::ScreenTop, &["H"], "Go to the top of the screen"),
+    (Mode::Normal, "screen_middle", Action::ScreenMiddle, &["M"], "Go to the middle of the screen"),
+    (Mode::Normal, "screen_bottom", Action::ScreenBottom, &["L"], "Go to the bottom of the screen"),
+    (Mode::Normal, "open", Action::Open, &["enter"], "Open directory / cd to a file's directory"),
+    (Mode::Normal, "enter_dir", Action::EnterDir, &["right"], "Open directory (ff pick)"),
+    (Mode::Normal, "parent", Action::Parent, &["backspace"], "Go to parent directory"),
+    (Mode::Normal, "search", Action::Search, &["space"], "Start/stop search"),
+    (Mode::Normal, "jump", Action::Jump, &["/"], "Jump to any directory in history"),
+    (Mode::Normal, "mark", Action::Mark, &["tab"], "Mark/unmark entry (ff pick --multi)"),
+    (Mode::Normal, "bind", Action::Bind, &["ctrl-b"], "Bind/edit command for current directory"),
+    (Mode::Normal, "toggle_files", Action::ToggleFiles, &["f"], "Toggle files/directories view"),
+    (Mode::Normal, "preview", Action::Preview, &["p"], "Toggle preview pane"),
+    (Mode::Normal, "preview_down", Action::PreviewDown, &["J"], "Scroll preview down"),
+    (Mode::Normal, "preview_up", Action::PreviewUp, &["K"], "Scroll preview up"),
+    (Mode::Normal, "help", Action::Help, &["h", "f1"], "Show help"),
+    (Mode::Normal, "quit", Action::Quit, &["q"], "Quit and cd into current directory"),
+    (Mode::Normal, "cancel", Action::Cancel, &["esc"], "Quit without changing directory"),
+    (Mode::Search, "up", Action::Up, &["up"], "Move selection up"),
+    (Mode::Search, "down", Action::Down, &["down"], "Move selection down"),
+    (Mode::Search, "page_down", Action::PageDown, &["pagedown"], "Move down a page"),
+    (Mode::Search, "page_up", Action::PageUp, &["pageup"], "Move up a page"),
+    (Mode::Search, "accept", Action::Accept, &["enter"], "Open the selected entry"),
+    (Mode::Search, "delete_char", Action::DeleteChar, &["backspace"], "Delete the character before the cursor"),
+    (Mode::Search, "cursor_left", Action::CursorLeft, &["left"], "Move the cursor left"),
+    (Mode::Search, "cursor_right", Action::CursorRight, &["right"], "Move the cursor right"),
+    (Mode::Search, "line_start", Action::LineStart, &["ctrl-a", "home"], "Move the cursor to the start"),
+    (Mode::Search, "line_end", Action::LineEnd, &["ctrl-e", "end"], "Move the cursor to the end"),
+    (Mode::Search, "word_left", Action::WordLeft, &["alt-b", "ctrl-left"], "Move the cursor back a word"),
+    (Mode::Search, "word_right", Action::WordRight, &["alt-f", "ctrl-right"], "Move the cursor forward a word"),
+    (Mode::Search, "delete_next_char", Action::DeleteNextChar, &["delete"], "Delete the character under the cursor"),
+    (Mode::Search, "delete_word", Action::DeleteWord, &["ctrl-w", "alt-backspace"], "Delete the word before the cursor"),
+    (Mode::Search, "delete_to_start", Action::DeleteToStart, &["ctrl-u"], "Delete to the start"),
+    (Mode::Search, "delete_to_end", Action::DeleteToEnd, &["ctrl-k"], "Delete to the end"),
+    (Mode::Search, "search", Action::Search, &["space"], "Stop searching when the query is empty"),
+    (Mode::Search, "toggle_match_mode", Action::ToggleMatchMode, &["ctrl-t"], "Toggle fuzzy/exact matching"),
+    (Mode::Search, "toggle_match_paths", Action::ToggleMatchPaths, &["ctrl-p"], "Toggle matching names/full paths"),
+    (Mode::Search, "mark", Action::Mark, &["tab"], "Mark/unmark entry (ff pick --multi)"),
+    (Mode::Search, "help", Action::Help, &["f1"], "Show the search keys"),
+    (Mode::Search, "cancel", Action::Cancel, &["esc"], "Stop searching"),
+    (Mode::Bind, "accept", Action::Accept, &["enter"], "Save both commands"),
+    (Mode::Bind, "delete_char", Action::DeleteChar, &["backspace"], "Delete the character before the cursor"),
+    (Mode::Bind, "cursor_left", Action::CursorLeft, &["left"], "Move the cursor left"),
+    (Mode::Bind, "cursor_right", Action::CursorRight, &["right"], "Move the cursor right"),
+    (Mode::Bind, "line_start", Action::LineStart, &["ctrl-a", "home"], "Move the cursor to the start"),
+    (Mode::Bind, "line_end", Action::LineEnd, &["ctrl-e", "end"], "Move the cursor to the end"),
+    (Mode::Bind, "word_left", Action::WordLeft, &["alt-b", "ctrl-left"], "Move the cursor back a word"),
+    (Mode::Bind, "word_right", Action::WordRight, &["alt-f", "ctrl-right"], "Move the cursor forward a word"),
+    (Mode::Bind, "delete_next_char", Action::DeleteNextChar, &["delete"], "Delete the character under the cursor"),
+    (Mode::Bind, "delete_word", Action::DeleteWord, &["ctrl-w", "alt-backspace"], "Delete the word before the cursor"),
+    (Mode::Bind, "delete_to_start", Action::DeleteToStart, &["ctrl-u"], "Delete to the start"),
+    (Mode::Bind, "delete_to_end", Action::DeleteToEnd, &["ctrl-k"], "Delete to the end"),
+    (Mode::Bind, "switch_field", Action::SwitchField, &["tab", "shift-tab", "up", "down"], "Switch between the enter and leave commands"),
+    (Mode::Bind, "toggle_inherit", Action::ToggleInherit, &["alt-i"], "Toggle whether the binding applies to subdirectories"),
+    (Mode::Bind, "toggle_extend", Action::ToggleExtend, &["alt-e"], "Toggle between extending and overriding an inherited binding"),
+    (Mode::Bind, "bind", Action::Bind, &["ctrl-b"], "Close the prompt"),
+    (Mode::Bind, "help", Action::Help, &["f1"], "Show the bind keys"),
+    (Mode::Bind, "cancel", Action::Cancel, &["esc"], "Close the prompt"),
+    (Mode::Help, "up", Action::Up, &["k", "up"], "Scroll up"),
+    (Mode::Help, "down", Action::Down, &["j", "down"], "Scroll down"),
+    (Mode::Help, "top", Action::Top, &["g g", "home"], "Scroll to the top"),
+    (Mode::Help, "bottom", Action::Bottom, &["G", "end"], "Scroll to the bottom"),
+    (Mode::Help, "page_down", Action::PageDown, &["pagedown"], "Scroll down a page"),
+    (Mode::Help, "page_up", Action::PageUp, &["pageup"], "Scroll up a page"),
+    (Mode::Help, "filter", Action::Filter, &["/"], "Filter help (typed with the search keys)"),
+    (Mode::Help, "help", Action::Help, &["h", "f1"], "Close help"),
+    (Mode::Help, "cancel", Action::Cancel, &["esc", "q"], "Clear the filter / close help"),
+    (Mode::Trust, "allow", Action::Allow, &["a", "y"], "Allow the command and run it"),
+    (Mode::Trust, "deny", Action::Deny, &["d"], "Deny the command and jump without it"),
+    (Mode::Trust, "skip", Action::Skip, &["s", "n"], "Jump without running it this time"),
+    (Mode::Trust, "cancel", Action::Cancel, &["esc"], "Go back to the navigator"),
+  ];
+
+  #[derive(PartialEq, Debug)]
+  pub enum Resolved {
+    Action(Action),
+    Pending,
+    Unbound,
+  }
+
+  enum Lookup {
+    Action(Action),
+    Prefix,
+    Missing,
+  }
+
+  fn conflicts(a: &[Key], b: &[Key]) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+  }
+
+  struct Binding {
+    keys: Vec<Key>,
+    name: &'static str,
+    action: Action,
+  }
+
+  pub struct Keymap {
+    bindings: HashMap<Mode, Vec<Binding>>,
+  }
+
+  impl Default for Keymap {
+    fn default() -> Self {
+      Keymap::new(&[]).expect("default key bindings are valid")
+    }
+  }
+
+  impl Keymap {
+    pub fn new(overrides: &[(Mode, String, Vec<String>)]) -> Result<Self, String> {
+      let mut bindings = HashMap::new();
+
+      for mode in Mode::ALL {
+        let mut explicit: Vec<Binding> = Vec::new();
+        for (override_mode, name, sequences) in overrides {
+          if *override_mode != mode {
+            continue;
+          }
+          let Some(&(_, name, action, _, _)) = ACTIONS.iter().find(|entry| entry.0 == mode && entry.1 == name) else {
+            return Err(format!("[keys.{}] unknown action '{}'", mode.name(), name));
+          };
+          for text in sequences {
+            let keys = Key::parse_sequence(text)
+              .ok_or_else(|| format!("[keys.{}] invalid key '{}' for {}", mode.name(), text, name))?;
+            if let Some(other) = explicit.iter().find(|other| conflicts(&keys, &other.keys)) {
+              return Err(format!(
+                "[keys.{}] '{}' for {} conflicts with a binding for {}",
+                mode.name(),
+                text,
+                name,
+                other.name
+              ));
+            }
+            explicit.push(Binding { keys, name, action });
+          }
+        }
+
+        let mut mode_bindings = Vec::new();
+        for &(_, name, action, defaults, _) in ACTIONS.iter().filter(|entry| entry.0 == mode) {
+          if overrides.iter().any(|(override_mode, override_name, _)| *override_mode == mode && override_name == name) {
+            continue;
+          }
+          for text in defaults {
+            let keys = Key::parse_sequence(text).ok_or_else(|| format!("invalid default key '{}'", text))?;
+            if !explicit.iter().any(|other| conflicts(&keys, &other.keys)) {
+              mode_bindings.push(Binding { keys, name, action });
+            }
+          }
+        }
+        mode_bindings.extend(explicit);
+        bindings.insert(mode, mode_bindings);
+      }
+
+      Ok(Keymap { bindings })
+    }
+
+    fn lookup(&self, mode: Mode, keys: &[Key]) -> Lookup {
+      let mut lookup = Lookup::Missing;
+      for binding in &self.bindings[&mode] {
+        if binding.keys == keys {
+          return Lookup::Action(binding.action);
+        }
+        if binding.keys.starts_with(keys) {
+          lookup = Lookup::Prefix;
+        }
+      }
+      lookup
+    }
+
+    pub fn resolve(&self, mode: Mode, pending: &mut Vec<Key>, key: Key) -> (Vec<Key>, Resolved) {
+      if !pending.is_empty() && key.code == KeyCode::Esc && key.modifiers.is_empty() {
+        pending.clear();
+        return (Vec::new(), Resolved::Pending);
+      }
+
+      let mut abandoned = Vec::new();
+      pending.push(key);
+      loop {
+        match self.lookup(mode, pending) {
+          Lookup::Action(action) => {
+            pending.clear();
+            return (abandoned, Resolved::Action(action));
+          }
+          Lookup::Prefix => return (abandoned, Resolved::Pending),
+          Lookup::Missing if pending.len() > 1 => {
+            abandoned.extend(pending.drain(..pending.len() - 1));
+          }
+          Lookup::Missing => {
+            pending.clear();
+            return (abandoned, Resolved::Unbound);
+          }
+        }
+      }
+    }
+
+    pub fn help(&self, mode: Mode) -> Vec<(String, &'static str)> {
+      ACTIONS
+        .iter()
+        .filter(|entry| entry.0 == mode)
+        .filter_map(|&(_, name, _, _, description)| {
+          let labels: Vec<String> = self.bindings[&mode]
+            .iter()
+            .filter(|binding| binding.name == name)
+            .map(|binding| sequence_label(&binding.keys))
+            .collect();
+          if labels.is_empty() {
+            return None;
+          }
+          Some((labels.join("/"), description))
+        })
+        .collect()
+    }
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+
+    fn pressed(code: KeyCode, modifiers: KeyModifiers) -> Key {
+      Key::from_event(&KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn shift_uppercases_characters() {
+      assert_eq!(Key::parse("shift-g"), Some(pressed(KeyCode::Char('G'), KeyModifiers::SHIFT)));
+      assert_eq!(Key::parse("shift-g"), Key::parse("G"));
+      assert_ne!(Key::parse("shift-g"), Key::parse("g"));
+      assert_eq!(Key::parse("alt-shift-b"), Some(pressed(KeyCode::Char('B'), KeyModifiers::ALT | KeyModifiers::SHIFT)));
+      assert_eq!(Key::parse("shift-tab"), Some(pressed(KeyCode::BackTab, KeyModifiers::SHIFT)));
+      assert_eq!(Key::parse("shift-1"), None);
+      assert_eq!(Key::parse("shift-/"), None);
+    }
+
+    #[test]
+    fn control_characters_ignore_case() {
+      assert_eq!(Key::parse("ctrl-B"), Some(pressed(KeyCode::Char('b'), KeyModifiers::CONTROL)));
+      assert_eq!(Key::parse("ctrl-b"), Some(pressed(KeyCode::Char('B'), KeyModifiers::CONTROL | KeyModifiers::SHIFT)));
+      assert_eq!(Key::parse("alt-B"), Some(pressed(KeyCode::Char('B'), KeyModifiers::ALT | KeyModifiers::SHIFT)));
+      assert_ne!(Key::parse("alt-B"), Key::parse("alt-b"));
+    }
+
+    #[test]
+    fn abandoned_chords_are_handed_back() {
+      let keymap = Keymap::new(&[(Mode::Search, String::from("cancel"), vec![String::from("j k")])]).unwrap();
+      let key = |text| Key::parse(text).unwrap();
+      let mut pending = Vec::new();
+
+      assert_eq!(keymap.resolve(Mode::Search, &mut pending, key("j")), (vec![], Resolved::Pending));
+      assert_eq!(keymap.resolve(Mode::Search, &mut pending, key("a")), (vec![key("j")], Resolved::Unbound));
+      assert!(pending.is_empty());
+
+      keymap.resolve(Mode::Search, &mut pending, key("j"));
+      assert_eq!(keymap.resolve(Mode::Search, &mut pending, key("j")), (vec![key("j")], Resolved::Pending));
+      assert_eq!(keymap.resolve(Mode::Search, &mut pending, key("k")), (vec![], Resolved::Action(Action::Cancel)));
+
+      keymap.resolve(Mode::Search, &mut pending, key("j"));
+      assert_eq!(
+        keymap.resolve(Mode::Search, &mut pending, key("enter")),
+        (vec![key("j")], Resolved::Action(Action::Accept))
+      );
+
+      keymap.resolve(Mode::Search, &mut pending, key("j"));
+      assert_eq!(keymap.resolve(Mode::Search, &mut pending, key("esc")), (vec![], Resolved::Pending));
+      assert!(pending.is_empty());
+
+      assert_eq!(key("j").typed(), Some('j'));
+      assert_eq!(key("ctrl-j").typed(), None);
+    }
+  }
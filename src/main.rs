@@ -1,24 +1,60 @@
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
   };
   use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame, Terminal,
   };
-  use serde_json::Value;
   use std::collections::HashMap;
   use std::env;
   use std::fs::{self, File, OpenOptions};
-  use std::io::{self, BufRead, BufReader, Read, Write};
+  use std::ffi::OsString;
+  use std::io::{self, IsTerminal, Read, Write};
+  use std::os::unix::ffi::{OsStrExt, OsStringExt};
   use std::path::{Path, PathBuf};
   use std::process::{Command, Stdio};
-  use std::time::{SystemTime, UNIX_EPOCH};
+  use std::time::{Duration, UNIX_EPOCH};
+  use unicode_width::UnicodeWidthStr;
+
+  mod bindings;
+  mod cli;
+  mod config;
+  mod editor;
+  mod help;
+  mod history;
+  mod keys;
+  mod matcher;
+  mod preview;
+  mod previewer;
+  mod shell;
+  mod store;
+  mod trust;
+
+  use bindings::{Binding, Bound};
+  use cli::BindAction;
+  use editor::LineEditor;
+  use help::HelpItem;
+  use history::{get_history_file_path, read_history, update_history, History, HistoryEntry};
+  use keys::{Action, Key, Mode, Resolved};
+  use matcher::MatchMode;
+  use preview::Preview;
+  use shell::Shell;
+  use previewer::Previewers;
+  use trust::Trust;
+  
+  const PREVIEW_POLL_INTERVAL: Duration = Duration::from_millis(50);
+  
+  struct TrustPrompt {
+    target: PathBuf,
+    bound: Bound,
+    trust: Trust,
+  }
   
   struct App {
     entries: Vec<(PathBuf, u64, bool)>, 
@@ -28,23 +64,52 @@ use crossterm::{
     should_quit: bool,
     show_help: bool,
     search_mode: bool,
-    search_query: String,
+    search_query: LineEditor,
+    global_search: bool,
+    saved_entries: Vec<(PathBuf, u64, bool)>,
+    match_mode: MatchMode,
+    match_paths: bool,
+    match_positions: HashMap<PathBuf, Vec<usize>>,
+    picker: bool,
+    candidates: bool,
+    multi: bool,
+    marked: Vec<PathBuf>,
     bind_mode: bool,
-    bind_command: String,
+    bind_enter: LineEditor,
+    bind_leave: LineEditor,
+    bind_leave_focused: bool,
+    bind_inherit: bool,
+    bind_extend: bool,
+    bind_shadowed: Option<Bound>,
+    pending_keys: Vec<Key>,
+    count: Option<usize>,
+    page_height: usize,
     show_files: bool,
+    show_preview: bool,
+    preview: Option<Preview>,
+    preview_scroll: u16,
+    previewers: Previewers,
     unfiltered_entries: Vec<(PathBuf, u64, bool)>,
     help_scroll_state: ListState,
     help_scroll_index: usize,
+    help_context: Mode,
+    help_filter: LineEditor,
+    help_filtering: bool,
+    trust_prompt: Option<TrustPrompt>,
+    skipped_bindings: Vec<String>,
+    history: History,
   }
   
   impl App {
-    fn new(current_dir: PathBuf, entries: Vec<(PathBuf, u64, bool)>) -> Self {
+    fn new(current_dir: PathBuf, entries: Vec<(PathBuf, u64, bool)>, history: History) -> Self {
       let mut list_state = ListState::default();
       list_state.select(Some(0));
       
       let mut help_scroll_state = ListState::default();
       help_scroll_state.select(Some(0));
       
+      let config = config::get();
+      
       App {
         entries,
         unfiltered_entries: Vec::new(),
@@ -54,65 +119,181 @@ use crossterm::{
         should_quit: false,
         show_help: false,
         search_mode: false,
-        search_query: String::new(),
+        search_query: LineEditor::default(),
+        global_search: false,
+        saved_entries: Vec::new(),
+        match_mode: config.match_mode,
+        match_paths: config.match_paths,
+        match_positions: HashMap::new(),
+        picker: false,
+        candidates: false,
+        multi: false,
+        marked: Vec::new(),
         bind_mode: false,
-        bind_command: String::new(),
-        show_files: false,
+        bind_enter: LineEditor::default(),
+        bind_leave: LineEditor::default(),
+        bind_leave_focused: false,
+        bind_inherit: false,
+        bind_extend: false,
+        bind_shadowed: None,
+        pending_keys: Vec::new(),
+        count: None,
+        page_height: 1,
+        show_files: config.show_files,
+        show_preview: config.show_preview,
+        preview: None,
+        preview_scroll: 0,
+        previewers: Previewers::load(),
         help_scroll_state,
         help_scroll_index: 0,
+        help_context: Mode::Normal,
+        help_filter: LineEditor::default(),
+        help_filtering: false,
+        trust_prompt: None,
+        skipped_bindings: Vec::new(),
+        history,
       }
     }
   
-    fn next(&mut self) {
+    fn select(&mut self, index: usize) {
       if !self.entries.is_empty() {
-        self.selected_index = (self.selected_index + 1) % self.entries.len();
+        self.selected_index = index.min(self.entries.len() - 1);
         self.list_state.select(Some(self.selected_index));
       }
     }
   
-    fn previous(&mut self) {
-      if !self.entries.is_empty() {
-        self.selected_index = if self.selected_index > 0 {
-          self.selected_index - 1
-        } else {
-          self.entries.len() - 1
-        };
-        self.list_state.select(Some(self.selected_index));
+    fn next(&mut self, count: usize) {
+      if config::get().wrap && self.selected_index + 1 == self.entries.len() {
+        self.select(0);
+      } else {
+        self.select(self.selected_index.saturating_add(count));
+      }
+    }
+  
+    fn previous(&mut self, count: usize) {
+      if config::get().wrap && self.selected_index == 0 {
+        self.select(self.entries.len().saturating_sub(1));
+      } else {
+        self.select(self.selected_index.saturating_sub(count));
       }
     }
+  
+    fn page_down(&mut self, rows: usize) {
+      self.select(self.selected_index.saturating_add(rows));
+    }
+  
+    fn page_up(&mut self, rows: usize) {
+      self.select(self.selected_index.saturating_sub(rows));
+    }
+  
+    fn visible_rows(&self) -> usize {
+      let offset = self.list_state.offset();
+      self.page_height.min(self.entries.len().saturating_sub(offset)).max(1)
+    }
+  
+    fn select_on_screen(&mut self, row: usize) {
+      self.select(self.list_state.offset() + row.min(self.visible_rows() - 1));
+    }
     
-    fn help_next(&mut self) {
-      self.help_scroll_index = self.help_scroll_index + 1;
-      self.help_scroll_state.select(Some(self.help_scroll_index));
+    fn help_items(&self) -> Vec<HelpItem> {
+      help::help_items(&config::get().keymap, self.help_context, self.help_filter.text())
     }
     
-    fn help_previous(&mut self) {
-      if self.help_scroll_index > 0 {
-        self.help_scroll_index -= 1;
-        self.help_scroll_state.select(Some(self.help_scroll_index));
-      }
+    fn select_help(&mut self, index: usize) {
+      self.help_scroll_index = index;
+      self.help_scroll_state.select(Some(index));
+    }
+    
+    fn scroll_help(&mut self, delta: isize) {
+      let last = self.help_items().len().saturating_sub(1);
+      let index = self.help_scroll_index.saturating_add_signed(delta).min(last);
+      self.select_help(index);
     }
   
-    fn toggle_help(&mut self) {
-      self.show_help = !self.show_help;
+    fn open_help(&mut self, context: Mode) {
+      self.show_help = true;
+      self.help_context = context;
+      self.help_filter = LineEditor::default();
+      self.help_filtering = false;
+      self.select_help(0);
+    }
+  
+    fn close_help(&mut self) {
+      self.show_help = false;
+      self.help_filter = LineEditor::default();
+      self.help_filtering = false;
+    }
+  
+    fn clear_help_filter(&mut self) {
+      self.help_filter = LineEditor::default();
+      self.help_filtering = false;
+      self.select_help(0);
     }
   
     fn toggle_files_dirs(&mut self) {
       self.show_files = !self.show_files;
     }
   
+    fn toggle_preview(&mut self) {
+      self.show_preview = !self.show_preview;
+      self.preview = None;
+    }
+  
+    fn refresh_preview(&mut self) {
+      if !self.show_preview {
+        return;
+      }
+      
+      match self.entries.get(self.selected_index) {
+        None => self.preview = None,
+        Some((path, _, _)) => {
+          if self.preview.as_ref().is_none_or(|preview| preview.path != *path) {
+            self.preview = None;
+            self.preview = Some(Preview::build(path, &self.history, &self.previewers));
+            self.preview_scroll = 0;
+          }
+        }
+      }
+      
+      if let Some(preview) = self.preview.as_mut() {
+        preview.poll();
+      }
+    }
+  
+    fn scroll_preview(&mut self, delta: i32) {
+      let line_count = self.preview.as_ref().map_or(0, |preview| preview.lines.len());
+      let max_scroll = line_count.saturating_sub(1).min(u16::MAX as usize) as i32;
+      self.preview_scroll = (self.preview_scroll as i32 + delta).clamp(0, max_scroll) as u16;
+    }
+  
     fn start_search(&mut self) {
       if !self.search_mode && !self.bind_mode {
         self.search_mode = true;
-        self.search_query = String::new();
+        self.search_query = LineEditor::default();
         self.unfiltered_entries = self.entries.clone();
       }
     }
   
-    fn start_bind(&mut self, current_command: String) {
+    fn start_global_search(&mut self) {
+      if !self.search_mode && !self.bind_mode {
+        self.search_mode = true;
+        self.global_search = true;
+        self.search_query = LineEditor::default();
+        self.saved_entries = self.entries.clone();
+        self.unfiltered_entries = get_history_entries(&self.history);
+        self.filter_entries();
+      }
+    }
+  
+    fn start_bind(&mut self, binding: Binding, shadowed: Option<Bound>) {
       if !self.search_mode && !self.bind_mode {
         self.bind_mode = true;
-        self.bind_command = current_command;
+        self.bind_enter = LineEditor::new(binding.enter);
+        self.bind_leave = LineEditor::new(binding.leave);
+        self.bind_leave_focused = false;
+        self.bind_inherit = binding.inherit;
+        self.bind_extend = binding.extend;
+        self.bind_shadowed = shadowed;
       } else if self.bind_mode {
         self.bind_mode = false;
       }
@@ -121,9 +302,16 @@ use crossterm::{
     fn end_search(&mut self) {
       if self.search_mode {
         self.search_mode = false;
-        self.search_query = String::new();
-        self.entries = self.unfiltered_entries.clone();
+        self.search_query = LineEditor::default();
+        self.entries = if self.global_search {
+          self.saved_entries.clone()
+        } else {
+          self.unfiltered_entries.clone()
+        };
+        self.global_search = false;
+        self.saved_entries = Vec::new();
         self.unfiltered_entries = Vec::new();
+        self.match_positions = HashMap::new();
         if !self.entries.is_empty() {
           self.selected_index = 0;
           self.list_state.select(Some(0));
@@ -134,45 +322,133 @@ use crossterm::{
     fn end_bind(&mut self) {
       if self.bind_mode {
         self.bind_mode = false;
-        self.bind_command = String::new();
+        self.bind_enter = LineEditor::default();
+        self.bind_leave = LineEditor::default();
       }
     }
   
-    fn update_search(&mut self, character: char) {
-      self.search_query.push(character);
-      self.filter_entries();
+    fn bind_editor(&mut self) -> &mut LineEditor {
+      if self.bind_leave_focused {
+        &mut self.bind_leave
+      } else {
+        &mut self.bind_enter
+      }
     }
   
-    fn update_bind(&mut self, character: char) {
-      self.bind_command.push(character);
+    fn bound_binding(&self) -> Binding {
+      Binding {
+        enter: self.bind_enter.text().to_string(),
+        leave: self.bind_leave.text().to_string(),
+        inherit: self.bind_inherit,
+        extend: self.bind_extend,
+      }
     }
   
-    fn backspace_search(&mut self) {
-      if !self.search_query.is_empty() {
-        self.search_query.pop();
-        self.filter_entries();
+    fn request_jump(&mut self, target: PathBuf) -> io::Result<Option<PathBuf>> {
+      if self.picker {
+        return Ok(Some(target));
+      }
+      
+      let jump = bindings::jump(&env::current_dir().unwrap_or_default(), &target)?;
+      
+      for bound in jump.leave.into_iter().chain(jump.enter) {
+        let key = bound.trust_key();
+        if self.skipped_bindings.contains(&key) {
+          continue;
+        }
+        let trust = trust::check(&key, &bound.binding.content())?;
+        if matches!(trust, Trust::New | Trust::Changed) {
+          self.trust_prompt = Some(TrustPrompt { target, bound, trust });
+          return Ok(None);
+        }
+      }
+      Ok(Some(target))
+    }
+  
+    fn paste(&mut self, text: &str) {
+      if self.show_help {
+        if self.help_filtering && self.help_filter.paste(text) {
+          self.select_help(0);
+        }
+      } else if self.search_mode {
+        if self.search_query.paste(text) {
+          self.filter_entries();
+        }
+      } else if self.bind_mode {
+        self.bind_editor().paste(text);
       }
     }
   
-    fn backspace_bind(&mut self) {
-      if !self.bind_command.is_empty() {
-        self.bind_command.pop();
+    fn toggle_mark(&mut self) {
+      if !self.multi || self.entries.is_empty() {
+        return;
+      }
+      let path = self.entries[self.selected_index].0.clone();
+      if let Some(index) = self.marked.iter().position(|marked| *marked == path) {
+        self.marked.remove(index);
+      } else {
+        self.marked.push(path);
       }
+      self.next(1);
+    }
+  
+    fn toggle_match_mode(&mut self) {
+      self.match_mode = self.match_mode.toggle();
+      self.filter_entries();
+    }
+  
+    fn toggle_match_paths(&mut self) {
+      self.match_paths = !self.match_paths;
+      self.filter_entries();
     }
   
     fn filter_entries(&mut self) {
-      let query = self.search_query.to_lowercase();
-      self.entries = self.unfiltered_entries
+      let query = matcher::Query::parse(self.search_query.text(), self.match_mode);
+      let full_path = self.global_search || self.match_paths;
+      let mut matches: Vec<_> = self.unfiltered_entries
         .iter()
-        .filter(|(path, _, _)| {
-          if let Some(name) = path.file_name() {
-            name.to_string_lossy().to_lowercase().contains(&query)
+        .filter_map(|entry| {
+          let (haystack, name_start) = if self.candidates {
+            (entry.0.to_string_lossy().to_string(), 0)
+          } else if full_path {
+            let name = entry.0.file_name()?.to_string_lossy().to_string();
+            let text = display_path(&entry.0);
+            let name_start = text.chars().count().saturating_sub(name.chars().count());
+            (text, name_start)
           } else {
-            false
+            (entry.0.file_name()?.to_string_lossy().to_string(), 0)
+          };
+          
+          let found = query.matches(&haystack)?;
+          let mut score = found.score;
+          if full_path && !self.candidates && found.positions.iter().all(|&position| position >= name_start) {
+            score += config::get().ranking.name_bonus;
           }
+          
+          let positions = if self.global_search {
+            found.positions
+          } else {
+            found.positions
+              .into_iter()
+              .filter(|&position| position >= name_start)
+              .map(|position| position - name_start)
+              .collect()
+          };
+          
+          Some((matcher::combined_score(score, entry.1), entry.clone(), positions))
         })
-        .cloned()
         .collect();
+      
+      if !query.is_empty() {
+        matches.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+      }
+      
+      self.match_positions = HashMap::new();
+      self.entries = Vec::new();
+      for (_, entry, positions) in matches {
+        self.match_positions.insert(entry.0.clone(), positions);
+        self.entries.push(entry);
+      }
   
       if !self.entries.is_empty() {
         self.selected_index = 0;
@@ -182,220 +458,709 @@ use crossterm::{
   }
   
   fn main() -> io::Result<()> {
-    if let Err(e) = run_app() {
-      eprintln!("Error: {}", e);
+    let mut args: Vec<OsString> = env::args_os().skip(1).collect();
+  
+    let config_file = match cli::take_config_option(&mut args) {
+      Ok(config_file) => config_file,
+      Err(message) => {
+        eprintln!("ff: {}", message);
+        eprintln!("{}", cli::USAGE);
+        std::process::exit(2);
+      }
+    };
+    
+    if let Err(e) = config::init(config_file) {
+      eprintln!("ff: {}", e);
+      std::process::exit(2);
+    }
+  
+    let command = match cli::parse_args(&args) {
+      Ok(command) => command,
+      Err(message) => {
+        eprintln!("ff: {}", message);
+        eprintln!("{}", cli::USAGE);
+        std::process::exit(2);
+      }
+    };
+  
+    let result = match command {
+      cli::Command::Help => {
+        println!("{}", cli::USAGE);
+        Ok(0)
+      }
+      cli::Command::Init { shell, hook } => {
+        print!("{}", shell.init_script(hook));
+        Ok(0)
+      }
+      cli::Command::Add(paths) => run_add(&paths),
+      cli::Command::Bind { action, dir } => run_bind(action, dir.as_deref()),
+      cli::Command::Query { list, score, print0, keywords } => run_query(list, score, print0, &keywords),
+      cli::Command::Jump(keywords) => run_jump(&keywords),
+      cli::Command::Pick { multi, print0, history, .. } if !io::stdin().is_terminal() => {
+        run_candidates(multi, print0, history)
+      }
+      cli::Command::Pick { files, multi, print0, .. } => run_pick(files, multi, print0),
+      cli::Command::Interactive if !io::stdin().is_terminal() => run_candidates(false, false, false),
+      cli::Command::Interactive => run_app(None).map(|_| 0),
+    };
+  
+    match result {
+      Ok(0) => {}
+      Ok(code) => std::process::exit(code),
+      Err(e) => {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+      }
     }
     Ok(())
   }
   
-  fn run_app() -> io::Result<()> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+  fn find_matches(history: &History, keywords: &[String]) -> Vec<(PathBuf, u64)> {
+    let now = history::now();
+    let mut matches: Vec<(PathBuf, u64)> = history
+      .iter()
+      .filter(|(path, _)| matcher::keyword_match(&path.to_string_lossy(), keywords))
+      .filter(|(path, _)| path.is_dir())
+      .map(|(path, entry)| (path.clone(), entry.frecency(now)))
+      .collect();
+    
+    matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    matches
+  }
   
+  fn run_add(paths: &[PathBuf]) -> io::Result<i32> {
+    let history_file = get_history_file_path()?;
+    
+    for path in paths {
+      if let Ok(path) = fs::canonicalize(path)
+        && path.is_dir()
+      {
+        update_history(&history_file, &path)?;
+      }
+    }
+    
+    Ok(0)
+  }
+  
+  fn run_bind(action: BindAction, dir: Option<&Path>) -> io::Result<i32> {
+    let dir = match dir {
+      Some(dir) => fs::canonicalize(dir)?,
+      None => env::current_dir()?,
+    };
+    
+    let effective = bindings::effective(&dir)?;
+    if effective.is_empty() {
+      eprintln!("ff: no command is bound to {}", dir.display());
+      return Ok(1);
+    }
+    
+    for (index, bound) in effective.iter().rev().enumerate() {
+      let key = bound.trust_key();
+      match action {
+        BindAction::Allow => trust::set(&key, &bound.binding.content(), true)?,
+        BindAction::Deny => trust::set(&key, &bound.binding.content(), false)?,
+        BindAction::Show => {
+          let trust = match trust::check(&key, &bound.binding.content())? {
+            Trust::Allowed => "allowed",
+            Trust::Denied => "denied",
+            Trust::New => "new",
+            Trust::Changed => "changed",
+          };
+          if index > 0 {
+            println!();
+          }
+          match &bound.pattern {
+            Some(pattern) if bound.dir != dir => println!("rule: {} at {}", pattern.label(), bound.dir.display()),
+            Some(pattern) => println!("rule: {}", pattern.label()),
+            None => println!("rule: path {}", bound.dir.display()),
+          }
+          println!("trust: {}", trust);
+          if !bound.binding.enter.is_empty() {
+            println!("enter: {}", bound.binding.enter);
+          }
+          if !bound.binding.leave.is_empty() {
+            println!("leave: {}", bound.binding.leave);
+          }
+          if bound.binding.inherit {
+            println!("inherit: yes");
+          }
+          if bound.binding.extend {
+            println!("extend: yes");
+          }
+        }
+      }
+    }
+    Ok(0)
+  }
+  
+  fn run_query(list: bool, score: bool, print0: bool, keywords: &[String]) -> io::Result<i32> {
+    let history = read_history(&get_history_file_path()?)?;
+    let matches = find_matches(&history, keywords);
+    let shown = if list { &matches[..] } else { &matches[..matches.len().min(1)] };
+    
+    let mut stdout = io::stdout().lock();
+    for (path, frecency) in shown {
+      if score {
+        write!(stdout, "{:>8} ", frecency)?;
+      }
+      stdout.write_all(path.as_os_str().as_encoded_bytes())?;
+      stdout.write_all(if print0 { b"\0" } else { b"\n" })?;
+    }
+    
+    Ok(if matches.is_empty() { 1 } else { 0 })
+  }
+  
+  fn run_jump(keywords: &[String]) -> io::Result<i32> {
+    if let [target] = keywords
+      && Path::new(target).is_dir()
+    {
+      let history_file = get_history_file_path()?;
+      enter_directory(&history_file, &fs::canonicalize(target)?, &[])?;
+      return Ok(0);
+    }
+    
     let history_file = get_history_file_path()?;
     let history = read_history(&history_file)?;
     let current_dir = env::current_dir()?;
-    let entries = get_sorted_entries(&current_dir, &history, false)?;
-    let mut app = App::new(current_dir, entries);
+    let matches: Vec<(PathBuf, u64)> = find_matches(&history, keywords)
+      .into_iter()
+      .filter(|(path, _)| *path != current_dir)
+      .collect();
+    
+    match matches.as_slice() {
+      [] => {
+        eprintln!("ff: no match for '{}'", keywords.join(" "));
+        Ok(1)
+      }
+      [(path, _)] => enter_directory(&history_file, path, &[]).map(|_| 0),
+      [(path, best), (_, runner_up), ..] if *best >= runner_up.saturating_mul(config::get().ranking.jump_ratio) => {
+        enter_directory(&history_file, path, &[]).map(|_| 0)
+      }
+      _ => run_app(Some(keywords.join(" "))).map(|_| 0),
+    }
+  }
   
-    let res = run_ui(&mut terminal, &mut app, &history_file);
+  fn open_tty() -> io::Result<File> {
+    OpenOptions::new().read(true).write(true).open("/dev/tty")
+  }
+  
+  fn run_tui(app: &mut App) -> io::Result<Option<PathBuf>> {
+    enable_raw_mode()?;
+    let mut tty = io::BufWriter::new(open_tty()?);
+    execute!(tty, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
+    let backend = CrosstermBackend::new(tty);
+    let mut terminal = Terminal::new(backend)?;
+  
+    let res = run_ui(&mut terminal, app);
   
     disable_raw_mode()?;
     execute!(
       terminal.backend_mut(),
       LeaveAlternateScreen,
-      DisableMouseCapture
+      DisableMouseCapture,
+      DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
   
-    if let Some(selected_dir) = res? {
-      update_history(&history_file, &selected_dir)?;
+    res
+  }
   
-      let shell = env::var("SHELL").unwrap_or_else(|_| String::from("/bin/bash"));
-      
-      let custom_command = get_custom_script(&selected_dir)?;
-      
-      let mut shell_command = format!("cd '{}'", selected_dir.display());
-      
-      if let Some(cmd) = custom_command {
-        shell_command.push_str(&format!(" && {}", cmd));
+  fn run_pick(files: bool, multi: bool, print0: bool) -> io::Result<i32> {
+    let history = read_history(&get_history_file_path()?)?;
+    let current_dir = env::current_dir()?;
+    let entries = get_sorted_entries(&current_dir, &history, files)?;
+    let mut app = App::new(current_dir, entries, history);
+    app.show_files = files;
+    app.picker = true;
+    app.multi = multi;
+  
+    let Some(selected) = run_tui(&mut app)? else {
+      return Ok(130);
+    };
+  
+    let picked = if app.marked.is_empty() {
+      vec![selected]
+    } else {
+      app.marked
+    };
+  
+    print_paths(&picked, print0)?;
+    Ok(0)
+  }
+  
+  fn read_candidates() -> io::Result<Vec<PathBuf>> {
+    let mut input = Vec::new();
+    io::stdin().lock().read_to_end(&mut input)?;
+    
+    Ok(input
+      .split(|&byte| byte == b'\n')
+      .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+      .filter(|line| !line.is_empty())
+      .map(|line| PathBuf::from(OsString::from_vec(line.to_vec())))
+      .collect())
+  }
+  
+  fn run_candidates(multi: bool, print0: bool, boost: bool) -> io::Result<i32> {
+    let history = read_history(&get_history_file_path()?)?;
+    let current_dir = env::current_dir()?;
+    
+    let mut entries: Vec<(PathBuf, u64, bool)> = read_candidates()?
+      .into_iter()
+      .map(|line| (line, 0, false))
+      .collect();
+    
+    if boost {
+      let now = history::now();
+      for entry in &mut entries {
+        let absolute = current_dir.join(&entry.0);
+        if let Some(record) = history.get(&absolute) {
+          entry.1 = record.frecency(now);
+        }
       }
-      
-      shell_command.push_str(&format!(" && exec {}", shell));
-      
-      let status = Command::new(&shell)
-        .arg("-c")
-        .arg(shell_command)
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .status()?;
+      entries.sort_by_key(|entry| std::cmp::Reverse(entry.1));
+    }
+    
+    let mut app = App::new(current_dir, entries, history);
+    app.picker = true;
+    app.candidates = true;
+    app.multi = multi;
+    app.start_search();
+  
+    let Some(selected) = run_tui(&mut app)? else {
+      return Ok(130);
+    };
   
-      if !status.success() {
-        eprintln!("Failed to change directory.");
+    let picked = if app.marked.is_empty() {
+      vec![selected]
+    } else {
+      app.marked
+    };
+  
+    print_paths(&picked, print0)?;
+    Ok(0)
+  }
+  
+  fn print_paths(paths: &[PathBuf], print0: bool) -> io::Result<()> {
+    let separator = if print0 { b'\0' } else { b'\n' };
+    let mut stdout = io::stdout().lock();
+    for path in paths {
+      stdout.write_all(path.as_os_str().as_encoded_bytes())?;
+      stdout.write_all(&[separator])?;
+    }
+    stdout.flush()
+  }
+  
+  fn run_app(initial_query: Option<String>) -> io::Result<()> {
+    let history_file = get_history_file_path()?;
+    let history = read_history(&history_file)?;
+    let current_dir = env::current_dir()?;
+    let entries = get_sorted_entries(&current_dir, &history, config::get().show_files)?;
+    let mut app = App::new(current_dir, entries, history);
+    
+    if let Some(query) = initial_query {
+      app.start_global_search();
+      app.search_query = LineEditor::new(query);
+      app.filter_entries();
+    }
+  
+    if let Some(selected_dir) = run_tui(&mut app)? {
+      enter_directory(&history_file, &selected_dir, &app.skipped_bindings)?;
+    }
+  
+    Ok(())
+  }
+  
+  fn enter_directory(history_file: &Path, selected_dir: &Path, skipped: &[String]) -> io::Result<()> {
+    let handoff = shell::detect_handoff();
+    
+    if !handoff.as_ref().is_some_and(|handoff| handoff.hooked) {
+      update_history(history_file, selected_dir)?;
+    }
+  
+    let jump = bindings::jump(&env::current_dir().unwrap_or_default(), selected_dir)?;
+    let mut leave_commands = Vec::new();
+    for bound in jump.leave {
+      if let Some(binding) = trusted_binding(bound, skipped)? {
+        leave_commands.push(binding.leave);
+      }
+    }
+    let mut enter_commands = Vec::new();
+    for bound in jump.enter {
+      if let Some(binding) = trusted_binding(bound, skipped)? {
+        enter_commands.push(binding.enter);
       }
     }
   
+    if let Some(handoff) = handoff {
+      return handoff.write(&leave_commands, selected_dir, &enter_commands);
+    }
+  
+    let shell_path = env::var_os("SHELL").unwrap_or_else(|| OsString::from("/bin/bash"));
+    let shell = Shell::from_path(Path::new(&shell_path));
+    
+    let mut shell_command = shell.jump_script(&leave_commands, selected_dir, &enter_commands);
+    shell_command.extend_from_slice(shell.and_then().as_bytes());
+    shell_command.extend_from_slice(b"exec ");
+    shell_command.extend(shell.quote(shell_path.as_bytes()));
+    
+    let status = Command::new(&shell_path)
+      .arg("-c")
+      .arg(OsString::from_vec(shell_command))
+      .stdin(Stdio::inherit())
+      .stdout(Stdio::inherit())
+      .stderr(Stdio::inherit())
+      .status()?;
+  
+    if !status.success() {
+      eprintln!("Failed to change directory.");
+    }
+  
     Ok(())
   }
   
   fn run_ui<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
-    history_file: &Path,
   ) -> io::Result<Option<PathBuf>> {
     loop {
+      app.refresh_preview();
       terminal.draw(|f| ui(f, app))?;
+      
+      if app.preview.as_ref().is_some_and(Preview::is_running) && !event::poll(PREVIEW_POLL_INTERVAL)? {
+        continue;
+      }
   
-      if let Event::Key(key) = event::read()? {
-        if app.search_mode {
-          match key.code {
-            KeyCode::Esc => {
+      let event = event::read()?;
+      if let Event::Paste(text) = &event {
+        app.paste(text);
+      }
+      if let Event::Key(key) = event {
+        let mode = if app.trust_prompt.is_some() {
+          Mode::Trust
+        } else if app.show_help {
+          Mode::Help
+        } else if app.search_mode {
+          Mode::Search
+        } else if app.bind_mode {
+          Mode::Bind
+        } else {
+          Mode::Normal
+        };
+        let keymap_mode = if app.help_filtering { Mode::Search } else { mode };
+        
+        let key = Key::from_event(&key);
+        let (abandoned, resolved) = config::get().keymap.resolve(keymap_mode, &mut app.pending_keys, key);
+        if matches!(keymap_mode, Mode::Search | Mode::Bind) {
+          let replayed: String = abandoned.iter().filter_map(Key::typed).collect();
+          app.paste(&replayed);
+        }
+        let action = match resolved {
+          Resolved::Pending => continue,
+          Resolved::Action(action) => Some(action),
+          Resolved::Unbound => None,
+        };
+        let typed = key.typed();
+        
+        let count = if mode == Mode::Normal {
+          if action.is_none()
+            && let Some(digit) = typed.and_then(|c| c.to_digit(10))
+            && (digit > 0 || app.count.is_some())
+          {
+            app.count = Some(app.count.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize));
+            continue;
+          }
+          if action == Some(Action::Cancel) && app.count.take().is_some() {
+            continue;
+          }
+          app.count.take()
+        } else {
+          None
+        };
+        let repeat = count.unwrap_or(1);
+        let half_page = (app.page_height / 2).max(1);
+        
+        match mode {
+          Mode::Trust => match (action, app.trust_prompt.take()) {
+            (Some(Action::Allow), Some(prompt)) => {
+              trust::set(&prompt.bound.trust_key(), &prompt.bound.binding.content(), true)?;
+              if let Some(dir) = app.request_jump(prompt.target)? {
+                return Ok(Some(dir));
+              }
+            }
+            (Some(Action::Deny), Some(prompt)) => {
+              trust::set(&prompt.bound.trust_key(), &prompt.bound.binding.content(), false)?;
+              if let Some(dir) = app.request_jump(prompt.target)? {
+                return Ok(Some(dir));
+              }
+            }
+            (Some(Action::Skip), Some(prompt)) => {
+              app.skipped_bindings.push(prompt.bound.trust_key());
+              if let Some(dir) = app.request_jump(prompt.target)? {
+                return Ok(Some(dir));
+              }
+            }
+            (Some(Action::Cancel), _) => {
+              app.skipped_bindings.clear();
+            }
+            (_, prompt) => {
+              app.trust_prompt = prompt;
+            }
+          },
+          Mode::Search => match action {
+            Some(Action::Cancel) if app.candidates => {
+              return Ok(None);
+            }
+            Some(Action::Cancel) => {
               app.end_search();
             }
-            KeyCode::Backspace => {
-              app.backspace_search();
-            }
-            KeyCode::Enter => {
-              if !app.entries.is_empty() {
-                let selected_path = app.entries[app.selected_index].0.clone();
-                let is_dir = app.entries[app.selected_index].2;
-                
-                app.end_search();
-                
-                if is_dir {
-                  app.current_dir = selected_path;
-                  let entries = get_sorted_entries(&app.current_dir, &read_history(history_file)?, app.show_files)?;
-                  app.entries = entries;
-                  app.selected_index = 0;
-                  app.list_state.select(Some(0));
-                } else {
-                  if let Some(parent) = selected_path.parent() {
-                    app.current_dir = parent.to_path_buf();
-                    let entries = get_sorted_entries(&app.current_dir, &read_history(history_file)?, app.show_files)?;
-                    app.entries = entries;
-                    app.selected_index = 0;
-                    app.list_state.select(Some(0));
-                  }
+            Some(Action::Accept) if !app.entries.is_empty() => {
+              let selected_path = app.entries[app.selected_index].0.clone();
+              let is_dir = app.entries[app.selected_index].2;
+              
+              if app.global_search || app.picker {
+                if let Some(dir) = app.request_jump(selected_path)? {
+                  return Ok(Some(dir));
                 }
+                continue;
+              }
+              
+              app.end_search();
+              
+              if is_dir {
+                app.current_dir = selected_path;
+                let entries = get_sorted_entries(&app.current_dir, &app.history, app.show_files)?;
+                app.entries = entries;
+                app.selected_index = 0;
+                app.list_state.select(Some(0));
+              } else if let Some(parent) = selected_path.parent() {
+                app.current_dir = parent.to_path_buf();
+                let entries = get_sorted_entries(&app.current_dir, &app.history, app.show_files)?;
+                app.entries = entries;
+                app.selected_index = 0;
+                app.list_state.select(Some(0));
               }
             }
-            KeyCode::Char(' ') => {
+            Some(Action::Search) if app.search_query.is_empty() && !app.candidates => {
               app.end_search();
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-              app.next();
+            Some(Action::ToggleMatchMode) => {
+              app.toggle_match_mode();
             }
-            KeyCode::Up | KeyCode::Char('k') => {
-              app.previous();
+            Some(Action::ToggleMatchPaths) => {
+              app.toggle_match_paths();
             }
-            KeyCode::Char(c) => {
-              app.update_search(c);
+            Some(Action::Mark) => {
+              app.toggle_mark();
             }
-            _ => {}
-          }
-        } else if app.bind_mode {
-          match key.code {
-            KeyCode::Esc => {
-              app.end_bind();
+            Some(Action::Down) => {
+              app.next(1);
+            }
+            Some(Action::Up) => {
+              app.previous(1);
+            }
+            Some(Action::PageDown) => {
+              app.page_down(app.page_height);
             }
-            KeyCode::Backspace => {
-              app.backspace_bind();
+            Some(Action::PageUp) => {
+              app.page_up(app.page_height);
             }
-            KeyCode::Enter => {
-              save_custom_script(&app.current_dir, &app.bind_command)?;
+            Some(Action::Help) => {
+              app.open_help(Mode::Search);
+            }
+            action => {
+              if app.search_query.handle(action, typed) {
+                app.filter_entries();
+              }
+            }
+          },
+          Mode::Bind => match action {
+            Some(Action::Cancel) | Some(Action::Bind) => {
               app.end_bind();
             }
-            KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Action::Accept) => {
+              bindings::save(&app.current_dir, &app.bound_binding())?;
               app.end_bind();
             }
-            KeyCode::Char(c) => {
-              app.update_bind(c);
+            Some(Action::Help) => {
+              app.open_help(Mode::Bind);
             }
-            _ => {}
-          }
-        } else if app.show_help {
-          match key.code {
-            KeyCode::Esc => {
-              app.show_help = false;
+            Some(Action::SwitchField) => {
+              app.bind_leave_focused = !app.bind_leave_focused;
+            }
+            Some(Action::ToggleInherit) => {
+              app.bind_inherit = !app.bind_inherit;
+            }
+            Some(Action::ToggleExtend) => {
+              app.bind_extend = !app.bind_extend;
+            }
+            action => {
+              app.bind_editor().handle(action, typed);
+            }
+          },
+          Mode::Help if app.help_filtering => match action {
+            Some(Action::Cancel) => {
+              app.clear_help_filter();
+            }
+            Some(Action::Accept) => {
+              app.help_filtering = false;
+            }
+            Some(Action::Down) => {
+              app.scroll_help(1);
+            }
+            Some(Action::Up) => {
+              app.scroll_help(-1);
+            }
+            Some(Action::PageDown) => {
+              app.scroll_help(app.page_height as isize);
+            }
+            Some(Action::PageUp) => {
+              app.scroll_help(-(app.page_height as isize));
+            }
+            action => {
+              if app.help_filter.handle(action, typed) {
+                app.select_help(0);
+              }
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-              app.help_next();
+          },
+          Mode::Help => match action {
+            Some(Action::Cancel) if !app.help_filter.is_empty() => {
+              app.clear_help_filter();
             }
-            KeyCode::Up | KeyCode::Char('k') => {
-              app.help_previous();
+            Some(Action::Cancel) | Some(Action::Help) => {
+              app.close_help();
             }
-            KeyCode::Char('h') => {
-              app.toggle_help();
+            Some(Action::Down) => {
+              app.scroll_help(1);
             }
-            KeyCode::Char('q') => {
-              app.show_help = false;
+            Some(Action::Up) => {
+              app.scroll_help(-1);
+            }
+            Some(Action::Top) => {
+              app.select_help(0);
+            }
+            Some(Action::Bottom) => {
+              app.scroll_help(isize::MAX);
+            }
+            Some(Action::PageDown) => {
+              app.scroll_help(app.page_height as isize);
+            }
+            Some(Action::PageUp) => {
+              app.scroll_help(-(app.page_height as isize));
+            }
+            Some(Action::Filter) => {
+              app.help_filtering = true;
             }
             _ => {}
-          }
-        } else {
-          match key.code {
-            KeyCode::Char('q') => {
-              return Ok(Some(app.current_dir.clone()));
+          },
+          Mode::Normal => match action {
+            Some(Action::Quit) => {
+              if let Some(dir) = app.request_jump(app.current_dir.clone())? {
+                return Ok(Some(dir));
+              }
             }
-            KeyCode::Char('h') => {
-              app.toggle_help();
-              app.help_scroll_index = 0;
-              app.help_scroll_state.select(Some(0));
+            Some(Action::Help) => {
+              app.open_help(Mode::Normal);
             }
-            KeyCode::Char('f') => {
+            Some(Action::ToggleFiles) => {
               app.toggle_files_dirs();
-              let entries = get_sorted_entries(&app.current_dir, &read_history(history_file)?, app.show_files)?;
+              let entries = get_sorted_entries(&app.current_dir, &app.history, app.show_files)?;
               app.entries = entries;
               app.selected_index = 0;
               app.list_state.select(Some(0));
             }
-            KeyCode::Char(' ') => {
+            Some(Action::Search) => {
               app.start_search();
             }
-            KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-              let current_command = get_custom_script(&app.current_dir)?.unwrap_or_default();
-              app.start_bind(current_command);
+            Some(Action::Preview) => {
+              app.toggle_preview();
+            }
+            Some(Action::PreviewDown) => {
+              app.scroll_preview(1);
+            }
+            Some(Action::PreviewUp) => {
+              app.scroll_preview(-1);
+            }
+            Some(Action::Jump) => {
+              app.start_global_search();
+            }
+            Some(Action::Bind) => {
+              let binding = bindings::get(&app.current_dir)?.unwrap_or_default();
+              let shadowed = bindings::shadowed(&app.current_dir)?;
+              app.start_bind(binding, shadowed);
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-              app.next();
+            Some(Action::Down) => {
+              app.next(repeat);
             }
-            KeyCode::Up | KeyCode::Char('k') => {
-              app.previous();
+            Some(Action::Up) => {
+              app.previous(repeat);
             }
-            KeyCode::Backspace => {
+            Some(Action::Top) => {
+              app.select(repeat - 1);
+            }
+            Some(Action::Bottom) => {
+              app.select(count.map_or(usize::MAX, |line| line - 1));
+            }
+            Some(Action::HalfPageDown) => {
+              app.page_down(half_page.saturating_mul(repeat));
+            }
+            Some(Action::HalfPageUp) => {
+              app.page_up(half_page.saturating_mul(repeat));
+            }
+            Some(Action::PageDown) => {
+              app.page_down(app.page_height.saturating_mul(repeat));
+            }
+            Some(Action::PageUp) => {
+              app.page_up(app.page_height.saturating_mul(repeat));
+            }
+            Some(Action::ScreenTop) => {
+              app.select_on_screen(repeat - 1);
+            }
+            Some(Action::ScreenMiddle) => {
+              app.select_on_screen((app.visible_rows() - 1) / 2);
+            }
+            Some(Action::ScreenBottom) => {
+              app.select_on_screen(app.visible_rows().saturating_sub(repeat));
+            }
+            Some(Action::Parent) => {
               if let Some(parent) = app.current_dir.parent() {
                 let new_dir = parent.to_path_buf();
-                let entries = get_sorted_entries(&new_dir, &read_history(history_file)?, app.show_files)?;
+                let entries = get_sorted_entries(&new_dir, &app.history, app.show_files)?;
                 app.current_dir = new_dir;
                 app.entries = entries;
                 app.selected_index = 0;
                 app.list_state.select(Some(0));
               }
             }
-            KeyCode::Enter => {
-              if !app.entries.is_empty() {
-                let selected_path = app.entries[app.selected_index].0.clone();
-                let is_dir = app.entries[app.selected_index].2;
-                
-                if is_dir {
-                  app.current_dir = selected_path;
-                  let entries = get_sorted_entries(&app.current_dir, &read_history(history_file)?, app.show_files)?;
-                  app.entries = entries;
-                  app.selected_index = 0;
-                  app.list_state.select(Some(0));
-                } else {
-                  if let Some(parent) = selected_path.parent() {
-                    app.current_dir = parent.to_path_buf();
-                    return Ok(Some(app.current_dir.clone()));
-                  }
-                }
+            Some(Action::Mark) => {
+              app.toggle_mark();
+            }
+            Some(Action::Open) if app.picker && !app.entries.is_empty() => {
+              return Ok(Some(app.entries[app.selected_index].0.clone()));
+            }
+            Some(Action::EnterDir) if app.picker && !app.entries.is_empty() && app.entries[app.selected_index].2 => {
+              app.current_dir = app.entries[app.selected_index].0.clone();
+              let entries = get_sorted_entries(&app.current_dir, &app.history, app.show_files)?;
+              app.entries = entries;
+              app.selected_index = 0;
+              app.list_state.select(Some(0));
+            }
+            Some(Action::Open) if !app.entries.is_empty() => {
+              let selected_path = app.entries[app.selected_index].0.clone();
+              let is_dir = app.entries[app.selected_index].2;
+              
+              if is_dir {
+                app.current_dir = selected_path;
+                let entries = get_sorted_entries(&app.current_dir, &app.history, app.show_files)?;
+                app.entries = entries;
+                app.selected_index = 0;
+                app.list_state.select(Some(0));
+              } else if let Some(parent) = selected_path.parent()
+                && let Some(dir) = app.request_jump(parent.to_path_buf())?
+              {
+                return Ok(Some(dir));
               }
             }
-            KeyCode::Esc => {
+            Some(Action::Cancel) => {
               if app.show_help {
                 app.show_help = false;
               } else {
@@ -404,13 +1169,15 @@ use crossterm::{
               }
             }
             _ => {}
-          }
+          },
         }
       }
     }
   }
   
   fn ui(f: &mut Frame, app: &mut App) {
+    let colors = &config::get().colors;
+    
     let chunks = if app.search_mode || app.bind_mode {
       Layout::default()
         .direction(Direction::Vertical)
@@ -418,7 +1185,7 @@ use crossterm::{
         .constraints(
           [
             Constraint::Min(1),
-            Constraint::Length(3),
+            Constraint::Length(if app.bind_mode { 5 } else { 3 }),
           ]
           .as_ref(),
         )
@@ -434,196 +1201,231 @@ use crossterm::{
     let current_dir_str = app.current_dir.display().to_string();
     
     if app.show_help {
-      let help_text = vec![
-        ListItem::new(Line::from(Span::styled("Keyboard Controls:", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)))),
-        ListItem::new(Line::from("")),
-        ListItem::new(Line::from(vec![
-          Span::styled("↑/k", Style::default().fg(Color::Yellow)),
-          Span::raw(" - Move selection up"),
-        ])),
-        ListItem::new(Line::from(vec![
-          Span::styled("↓/j", Style::default().fg(Color::Yellow)),
-          Span::raw(" - Move selection down"),
-        ])),
-        ListItem::new(Line::from(vec![
-          Span::styled("Backspace", Style::default().fg(Color::Yellow)),
-          Span::raw(" - Go to parent directory"),
-        ])),
-        ListItem::new(Line::from(vec![
-          Span::styled("Space", Style::default().fg(Color::Yellow)),
-          Span::raw(" - Start/stop search"),
-        ])),
-        ListItem::new(Line::from(vec![
-          Span::styled("Ctrl+b", Style::default().fg(Color::Yellow)),
-          Span::raw(" - Bind/edit command for current directory"),
-        ])),
-        ListItem::new(Line::from(vec![
-          Span::styled("f", Style::default().fg(Color::Yellow)),
-          Span::raw(" - Toggle files/directories view"),
-        ])),
-        ListItem::new(Line::from(vec![
-          Span::styled("h", Style::default().fg(Color::Yellow)),
-          Span::raw(" - Toggle help"),
-        ])),
-        ListItem::new(Line::from(vec![
-          Span::styled("q", Style::default().fg(Color::Yellow)),
-          Span::raw(" - Quit and cd into current directory"),
-        ])),
-        ListItem::new(Line::from(vec![
-          Span::styled("Esc", Style::default().fg(Color::Yellow)),
-          Span::raw(" - Close help / Quit"),
-        ])),
-      ];
+      let help_text: Vec<ListItem> = app
+        .help_items()
+        .into_iter()
+        .map(|item| match item {
+          HelpItem::Blank => ListItem::new(Line::from("")),
+          HelpItem::Heading(title) => ListItem::new(Line::from(Span::styled(
+            format!("{}:", title),
+            Style::default().fg(colors.help_heading).add_modifier(Modifier::BOLD),
+          ))),
+          HelpItem::Entry(keys, description) => ListItem::new(Line::from(vec![
+            Span::styled(keys, Style::default().fg(colors.help_key)),
+            Span::raw(format!(" - {}", description)),
+          ])),
+        })
+        .collect();
+      
+      let title = if app.help_filtering || !app.help_filter.is_empty() {
+        let label = format!("Help ({}) /", current_dir_str);
+        let label_width = label.width();
+        let (filter, cursor) = app.help_filter.view((chunks[0].width as usize).saturating_sub(label_width + 2));
+        if app.help_filtering && label_width + cursor + 2 < chunks[0].width as usize {
+          f.set_cursor_position((chunks[0].x + 1 + (label_width + cursor) as u16, chunks[0].y));
+        }
+        format!("{}{}", label, filter)
+      } else {
+        format!("Help ({})", current_dir_str)
+      };
   
       let help = List::new(help_text)
-        .block(Block::default().borders(Borders::ALL).title(format!("Help ({})", current_dir_str)))
+        .block(Block::default().borders(Borders::ALL).title(title))
         .highlight_style(
           Style::default()
-            .fg(Color::Black)
-            .bg(Color::LightCyan)
+            .fg(colors.selection_fg)
+            .bg(colors.selection_bg)
             .add_modifier(Modifier::BOLD),
         );
       
+      app.page_height = chunks[0].height.saturating_sub(2).max(1) as usize;
       f.render_stateful_widget(help, chunks[0], &mut app.help_scroll_state);
     } else {
       let items: Vec<ListItem> = app
         .entries
         .iter()
         .map(|(path, _, is_dir)| {
-          let name = path.file_name().unwrap_or_default().to_string_lossy();
+          let name = if app.candidates {
+            path.to_string_lossy().to_string()
+          } else if app.global_search {
+            display_path(path)
+          } else {
+            path.file_name().unwrap_or_default().to_string_lossy().to_string()
+          };
           let display_text = if *is_dir {
             format!("{}/", name)
           } else {
-            name.to_string()
+            name
           };
           
           let style = if *is_dir {
-            Style::default().fg(Color::Blue)
+            Style::default().fg(colors.directory)
           } else {
-            Style::default().fg(Color::White)
+            Style::default().fg(colors.file)
+          };
+          
+          let mut line = match app.match_positions.get(path) {
+            Some(positions) if !positions.is_empty() => highlighted_line(&display_text, positions, style),
+            _ => Line::from(vec![Span::styled(display_text, style)]),
           };
           
-          ListItem::new(Line::from(vec![Span::styled(display_text, style)]))
+          if app.multi {
+            let marker = if app.marked.contains(path) { "* " } else { "  " };
+            line.spans.insert(0, Span::styled(marker, Style::default().fg(colors.mark).add_modifier(Modifier::BOLD)));
+          }
+          
+          ListItem::new(line)
         })
         .collect();
   
+      let title = if app.candidates && !app.marked.is_empty() {
+        format!("Pick ({}/{}, {} marked)", app.entries.len(), app.unfiltered_entries.len(), app.marked.len())
+      } else if app.candidates {
+        format!("Pick ({}/{})", app.entries.len(), app.unfiltered_entries.len())
+      } else if app.global_search {
+        String::from("Jump (history)")
+      } else if app.picker && !app.marked.is_empty() {
+        format!("Pick: {} ({} marked)", current_dir_str, app.marked.len())
+      } else if app.picker {
+        format!("Pick: {}", current_dir_str)
+      } else {
+        current_dir_str
+      };
+  
       let dirs_list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title(current_dir_str))
+        .block(Block::default().borders(Borders::ALL).title(title))
         .highlight_style(
           Style::default()
-            .fg(Color::Black)
-            .bg(Color::LightCyan)
+            .fg(colors.selection_fg)
+            .bg(colors.selection_bg)
             .add_modifier(Modifier::BOLD),
         );
   
-      f.render_stateful_widget(dirs_list, chunks[0], &mut app.list_state);
+      let (list_area, preview_area) = split_preview(chunks[0], app.show_preview);
+      app.page_height = list_area.height.saturating_sub(2).max(1) as usize;
+      f.render_stateful_widget(dirs_list, list_area, &mut app.list_state);
+      
+      if let (Some(area), Some(preview)) = (preview_area, &app.preview) {
+        let title = preview.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let preview_text = Paragraph::new(preview.lines.clone())
+          .block(Block::default().borders(Borders::ALL).title(title))
+          .scroll((app.preview_scroll, 0));
+        f.render_widget(preview_text, area);
+      }
     }
     
     if app.search_mode {
+      let label = format!(
+        "{} ({}{}): ",
+        if app.global_search { "Jump" } else { "Search" },
+        app.match_mode.label(),
+        if app.match_paths && !app.global_search { ", paths" } else { "" }
+      );
+      let label_width = label.width();
+      let (query, cursor) = app.search_query.view((chunks[1].width as usize).saturating_sub(label_width + 2));
       let search_text = Paragraph::new(Line::from(vec![
-        Span::styled("Search: ", Style::default().fg(Color::Yellow)),
-        Span::raw(&app.search_query),
+        Span::styled(label, Style::default().fg(colors.prompt)),
+        Span::raw(query),
       ]))
       .block(Block::default().borders(Borders::ALL));
       
       f.render_widget(search_text, chunks[1]);
+      if !app.show_help && app.trust_prompt.is_none() {
+        f.set_cursor_position((chunks[1].x + 1 + (label_width + cursor) as u16, chunks[1].y + 1));
+      }
     } else if app.bind_mode {
-      let bind_text = Paragraph::new(Line::from(vec![
-        Span::styled("Bind: ", Style::default().fg(Color::Green)),
-        Span::raw(&app.bind_command),
-      ]))
-      .block(Block::default().borders(Borders::ALL));
+      let width = (chunks[1].width as usize).saturating_sub(9);
+      let leave_focused = app.bind_leave_focused;
+      let mut cursor_position = None;
+      let mut lines = Vec::new();
+      for (row, (label, editor, focused)) in [
+        ("Enter: ", &mut app.bind_enter, !leave_focused),
+        ("Leave: ", &mut app.bind_leave, leave_focused),
+      ]
+      .into_iter()
+      .enumerate()
+      {
+        let (command, cursor) = editor.view(width);
+        let mut label_style = Style::default().fg(colors.bind_prompt);
+        if focused {
+          label_style = label_style.add_modifier(Modifier::BOLD);
+          cursor_position = Some((chunks[1].x + 1 + (label.width() + cursor) as u16, chunks[1].y + 1 + row as u16));
+        }
+        lines.push(Line::from(vec![Span::styled(label, label_style), Span::raw(command.to_string())]));
+      }
+      let check = |on: bool| if on { "[x] " } else { "[ ] " };
+      lines.push(Line::from(vec![
+        Span::raw(check(app.bind_inherit)),
+        Span::raw("Applies to subdirectories   "),
+        Span::raw(check(app.bind_extend)),
+        Span::raw("Extends the inherited binding"),
+      ]));
+      let title = match &app.bind_shadowed {
+        Some(bound) if app.bind_extend => format!("Bind (extends {})", describe_bound(bound)),
+        Some(bound) => format!("Bind (overrides {})", describe_bound(bound)),
+        None => String::from("Bind"),
+      };
+      let bind_text = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
       
       f.render_widget(bind_text, chunks[1]);
-    }
-  }
-  
-  fn get_history_file_path() -> io::Result<PathBuf> {
-    let home_dir = match env::var("HOME") {
-      Ok(home) => PathBuf::from(home),
-      Err(_) => {
-        return Err(io::Error::new(
-          io::ErrorKind::NotFound,
-          "HOME environment variable not set"
-        ));
+      if let Some(position) = cursor_position
+        && !app.show_help
+        && app.trust_prompt.is_none()
+      {
+        f.set_cursor_position(position);
       }
-    };
-    
-    let config_file = home_dir.join(".ff_config");
-    
-    
-    if !config_file.exists() {
-      File::create(&config_file)?;
     }
     
-    Ok(config_file)
-  }
-  
-  fn read_history(history_file: &Path) -> io::Result<HashMap<PathBuf, u64>> {
-    let mut history = HashMap::new();
-    
-    if history_file.exists() {
-      let file = File::open(history_file)?;
-      let reader = BufReader::new(file);
-      
-      for line in reader.lines() {
-        let line = line?;
-        let parts: Vec<&str> = line.split('|').collect();
-        if parts.len() == 2 {
-          let path = PathBuf::from(parts[0]);
-          let timestamp: u64 = parts[1].parse().unwrap_or(0);
-          history.insert(path, timestamp);
-        }
-      }
+    if let Some(prompt) = &app.trust_prompt {
+      render_trust_prompt(f, prompt);
     }
-    
-    Ok(history)
   }
   
-  fn update_history(history_file: &Path, selected_dir: &Path) -> io::Result<()> {
-    let mut history = read_history(history_file)?;
-    
-    
-    let now = SystemTime::now()
-      .duration_since(UNIX_EPOCH)
-      .unwrap()
-      .as_secs();
-    
-    history.insert(selected_dir.to_path_buf(), now);
-    let mut current = selected_dir.to_path_buf();
-    let mut time_decrease = 1;
+  fn render_trust_prompt(f: &mut Frame, prompt: &TrustPrompt) {
+    let colors = &config::get().colors;
+    let change = if prompt.trust == Trust::Changed { "has changed" } else { "is new" };
     
-    while let Some(parent) = current.parent() {
-      let parent_path = parent.to_path_buf();
-      if parent_path == current {
-        break;
+    let mut lines = vec![
+      Line::from(format!("The binding for {} {}:", describe_bound(&prompt.bound), change)),
+      Line::from(""),
+    ];
+    let command_style = Style::default().fg(colors.bind_prompt).add_modifier(Modifier::BOLD);
+    for (label, command) in [("On enter: ", &prompt.bound.binding.enter), ("On leave: ", &prompt.bound.binding.leave)] {
+      if !command.is_empty() {
+        lines.push(Line::from(vec![Span::raw(label), Span::styled(command.as_str(), command_style)]));
       }
-      let parent_timestamp = history.get(&parent_path).copied().unwrap_or(0);
-      let new_timestamp = now - time_decrease;
-      
-      if parent_timestamp < new_timestamp {
-        history.insert(parent_path.clone(), new_timestamp);
-      }
-      
-      current = parent_path;
-      time_decrease += 1;
     }
+    lines.push(Line::from(""));
+    lines.extend(config::get().keymap.help(Mode::Trust).into_iter().map(|(keys, description)| {
+      Line::from(vec![
+        Span::styled(keys, Style::default().fg(colors.help_key)),
+        Span::raw(format!(" - {}", description)),
+      ])
+    }));
     
-    let mut file = OpenOptions::new()
-      .write(true)
-      .truncate(true)
-      .create(true)
-      .open(history_file)?;
+    let area = f.area();
+    let width = area.width.saturating_sub(4).min(80);
+    let inner_width = width.saturating_sub(2).max(1) as usize;
+    let text_height: usize = lines.iter().map(|line| line.width().div_ceil(inner_width).max(1)).sum();
+    let height = (text_height + 2).min(area.height as usize) as u16;
+    let popup = Rect::new(
+      area.x + (area.width - width) / 2,
+      area.y + (area.height - height) / 2,
+      width,
+      height,
+    );
     
-    for (path, timestamp) in &history {
-      writeln!(file, "{}|{}", path.to_string_lossy(), timestamp)?;
-    }
-    
-    Ok(())
+    let text = Paragraph::new(lines)
+      .wrap(Wrap { trim: false })
+      .block(Block::default().borders(Borders::ALL).title("Run bound command?"));
+    f.render_widget(Clear, popup);
+    f.render_widget(text, popup);
   }
   
-  fn get_sorted_entries(dir: &Path, history: &HashMap<PathBuf, u64>, show_files: bool) -> io::Result<Vec<(PathBuf, u64, bool)>> {
+  fn get_sorted_entries(dir: &Path, history: &History, show_files: bool) -> io::Result<Vec<(PathBuf, u64, bool)>> {
     let mut entries = Vec::new();
+    let now = history::now();
+    let child_scores = history::child_scores(history, dir, now);
     
     if let Ok(dir_entries) = fs::read_dir(dir) {
       for entry in dir_entries.filter_map(Result::ok) {
@@ -638,28 +1440,21 @@ use crossterm::{
           continue;
         }
         
-        let mut score = history.get(&path).copied().unwrap_or(0);
+        let mut score = child_scores.get(&path).copied().unwrap_or(0);
         
-        if score == 0 && !is_dir {
-          if let Ok(metadata) = fs::metadata(&path) {
-            if let Ok(modified) = metadata.modified() {
-              if let Ok(duration) = modified.duration_since(UNIX_EPOCH) {
-                score = duration.as_secs();
-              }
-            }
-          }
+        if score == 0 && !is_dir
+          && let Ok(metadata) = fs::metadata(&path)
+          && let Ok(modified) = metadata.modified()
+          && let Ok(duration) = modified.duration_since(UNIX_EPOCH)
+        {
+          score = HistoryEntry::single(duration.as_secs()).frecency(now);
         }
         
         if is_dir {
           let mut current_path = dir.to_path_buf();
           while let Some(parent) = current_path.parent() {
             if path == parent {
-              let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-              let boost = now - (now - score) / 4;
-              score = score.max(boost);
+              score += score / 4;
               break;
             }
             current_path = parent.to_path_buf();
@@ -672,7 +1467,7 @@ use crossterm::{
     
     entries.sort_by(|a, b| {
       if show_files {
-        return b.1.cmp(&a.1);
+        b.1.cmp(&a.1)
       } else {
         match (a.2, b.2) {
           (true, false) => return std::cmp::Ordering::Less,
@@ -680,89 +1475,111 @@ use crossterm::{
           _ => {}
         }
         
-        return b.1.cmp(&a.1);
+        b.1.cmp(&a.1)
       }
     });
     
     Ok(entries)
   }
   
-  fn get_scripts_file_path() -> io::Result<PathBuf> {
-    let home_dir = match env::var("HOME") {
-      Ok(home) => PathBuf::from(home),
-      Err(_) => {
-        return Err(io::Error::new(
-          io::ErrorKind::NotFound,
-          "HOME environment variable not set"
-        ));
-      }
-    };
+  fn get_history_entries(history: &History) -> Vec<(PathBuf, u64, bool)> {
+    let now = history::now();
+    let mut entries: Vec<(PathBuf, u64, bool)> = history
+      .iter()
+      .filter(|(path, _)| path.is_dir())
+      .map(|(path, entry)| (path.clone(), entry.frecency(now), true))
+      .collect();
     
-    Ok(home_dir.join(".ff_scripts"))
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.1));
+    entries
   }
   
-  fn get_custom_script(dir: &Path) -> io::Result<Option<String>> {
-    let scripts_file = get_scripts_file_path()?;
-    
-    if !scripts_file.exists() {
-      return Ok(None);
+  fn split_preview(area: Rect, show_preview: bool) -> (Rect, Option<Rect>) {
+    if !show_preview {
+      return (area, None);
     }
     
-    let mut file = File::open(scripts_file)?;
-    let mut content = String::new();
-    file.read_to_string(&mut content)?;
-    
-    let scripts: Result<Value, _> = serde_json::from_str(&content);
-    
-    match scripts {
-      Ok(Value::Object(map)) => {
-        let dir_str = dir.to_string_lossy().to_string();
-        
-        for (key, value) in map {
-          if key == dir_str {
-            if let Value::String(cmd) = value {
-              return Ok(Some(cmd));
-            }
-          }
-        }
-        
-        Ok(None)
-      },
-      _ => Ok(None),
-    }
-  }
-  
-  fn save_custom_script(dir: &Path, command: &str) -> io::Result<()> {
-    let scripts_file = get_scripts_file_path()?;
-    let dir_str = dir.to_string_lossy().to_string();
-    
-    let content = if scripts_file.exists() {
-      let mut file = File::open(&scripts_file)?;
-      let mut content = String::new();
-      file.read_to_string(&mut content)?;
-      content
+    let direction = if area.width >= 80 {
+      Direction::Horizontal
+    } else if area.height >= 16 {
+      Direction::Vertical
     } else {
-      "{}".to_string()
+      return (area, None);
     };
     
-    let mut scripts: serde_json::Value = serde_json::from_str(&content).unwrap_or(serde_json::json!({}));
+    let chunks = Layout::default()
+      .direction(direction)
+      .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+      .split(area);
+    (chunks[0], Some(chunks[1]))
+  }
+  
+  fn highlighted_line(text: &str, positions: &[usize], style: Style) -> Line<'static> {
+    let matched_style = style.fg(config::get().colors.matched).add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
     
-    if let Some(obj) = scripts.as_object_mut() {
-      if command.is_empty() {
-        obj.remove(&dir_str);
-      } else {
-        obj.insert(dir_str, serde_json::Value::String(command.to_string()));
+    for (index, c) in text.chars().enumerate() {
+      let matched = positions.contains(&index);
+      if matched != run_matched && !run.is_empty() {
+        let run_style = if run_matched { matched_style } else { style };
+        spans.push(Span::styled(std::mem::take(&mut run), run_style));
       }
+      run_matched = matched;
+      run.push(c);
+    }
+    if !run.is_empty() {
+      let run_style = if run_matched { matched_style } else { style };
+      spans.push(Span::styled(run, run_style));
     }
     
-    let mut file = OpenOptions::new()
-      .write(true)
-      .truncate(true)
-      .create(true)
-      .open(&scripts_file)?;
-    
-    let formatted = serde_json::to_string_pretty(&scripts)?;
-    file.write_all(formatted.as_bytes())?;
+    Line::from(spans)
+  }
+  
+  fn display_path(path: &Path) -> String {
+    if let Ok(home) = env::var("HOME")
+      && let Ok(rest) = path.strip_prefix(&home)
+    {
+      if rest.as_os_str().is_empty() {
+        return String::from("~");
+      }
+      return format!("~/{}", rest.display());
+    }
+    path.display().to_string()
+  }
+  
+  fn describe_bound(bound: &Bound) -> String {
+    match &bound.pattern {
+      Some(pattern) => format!("{} ({})", display_path(&bound.dir), pattern.label()),
+      None => display_path(&bound.dir),
+    }
+  }
+  
+  fn trusted_binding(bound: Bound, skipped: &[String]) -> io::Result<Option<Binding>> {
+    let key = bound.trust_key();
+    if skipped.contains(&key) {
+      return Ok(None);
+    }
+    let state = match trust::check(&key, &bound.binding.content())? {
+      Trust::Allowed => return Ok(Some(bound.binding)),
+      Trust::Denied => return Ok(None),
+      Trust::New => "new",
+      Trust::Changed => "changed",
+    };
     
-    Ok(())
-  }
\ No newline at end of file
+    let quoted = Shell::Sh.quote(bound.dir.as_os_str().as_bytes());
+    let quoted = String::from_utf8_lossy(&quoted);
+    match &bound.pattern {
+      Some(pattern) => eprintln!("ff: not running the {} command bound to {} by {}:", state, bound.dir.display(), pattern.label()),
+      None => eprintln!("ff: not running the {} command bound to {}:", state, bound.dir.display()),
+    }
+    if !bound.binding.enter.is_empty() {
+      eprintln!("  {}", bound.binding.enter);
+    }
+    if !bound.binding.leave.is_empty() {
+      eprintln!("  on leave: {}", bound.binding.leave);
+    }
+    eprintln!("ff: run 'ff bind allow {}' to trust it, or 'ff bind deny {}' to skip it quietly", quoted, quoted);
+    Ok(None)
+  }
@@ -0,0 +1,562 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+  pub enum MatchMode {
+    #[default]
+    Fuzzy,
+    Exact,
+  }
+
+  impl MatchMode {
+    pub fn toggle(self) -> Self {
+      match self {
+        MatchMode::Fuzzy => MatchMode::Exact,
+        MatchMode::Exact => MatchMode::Fuzzy,
+      }
+    }
+
+    pub fn label(self) -> &'static str {
+      match self {
+        MatchMode::Fuzzy => "fuzzy",
+        MatchMode::Exact => "exact",
+      }
+    }
+  }
+
+  #[derive(Clone, Debug, Default)]
+  pub struct Match {
+    pub score: i64,
+    pub positions: Vec<usize>,
+  }
+
+  const SCORE_MATCH: i64 = 16;
+  const SCORE_GAP_START: i64 = 3;
+  const SCORE_GAP_EXTENSION: i64 = 1;
+  const BONUS_CONSECUTIVE: i64 = 8;
+  const BONUS_SEPARATOR: i64 = 10;
+  const BONUS_BOUNDARY: i64 = 8;
+  const BONUS_CAMEL: i64 = 7;
+  const BONUS_FIRST_CHAR: i64 = 6;
+
+  fn bonus(chars: &[char], index: usize) -> i64 {
+    if index == 0 {
+      return BONUS_FIRST_CHAR + BONUS_BOUNDARY;
+    }
+    let prev = chars[index - 1];
+    let current = chars[index];
+    if prev == '/' {
+      BONUS_SEPARATOR
+    } else if matches!(prev, '_' | '-' | '.' | ' ') || (!prev.is_alphanumeric() && current.is_alphanumeric()) {
+      BONUS_BOUNDARY
+    } else if prev.is_lowercase() && current.is_uppercase() {
+      BONUS_CAMEL
+    } else {
+      0
+    }
+  }
+
+  fn run_score(chars: &[char], start: usize, len: usize) -> i64 {
+    if len == 0 {
+      return 0;
+    }
+    let first = bonus(chars, start);
+    let rest: i64 = (start + 1..start + len)
+      .map(|index| bonus(chars, index).max(first).max(BONUS_CONSECUTIVE))
+      .sum();
+    SCORE_MATCH * len as i64 + first * 2 + rest
+  }
+
+  fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+  }
+
+  pub fn exact_match(haystack: &str, needle: &str) -> Option<Match> {
+    let chars: Vec<char> = haystack.chars().collect();
+    let pattern: Vec<char> = needle.chars().map(fold).collect();
+    if pattern.is_empty() {
+      return Some(Match::default());
+    }
+    if pattern.len() > chars.len() {
+      return None;
+    }
+
+    let mut best: Option<Match> = None;
+    for start in 0..=chars.len() - pattern.len() {
+      let found = pattern
+        .iter()
+        .enumerate()
+        .all(|(offset, &c)| fold(chars[start + offset]) == c);
+      if !found {
+        continue;
+      }
+      let score = run_score(&chars, start, pattern.len());
+      if best.as_ref().is_none_or(|current| score > current.score) {
+        best = Some(Match {
+          score,
+          positions: (start..start + pattern.len()).collect(),
+        });
+      }
+    }
+    best
+  }
+
+  pub fn fuzzy_match(haystack: &str, needle: &str) -> Option<Match> {
+    let chars: Vec<char> = haystack.chars().collect();
+    let folded: Vec<char> = chars.iter().copied().map(fold).collect();
+    let pattern: Vec<char> = needle.chars().map(fold).collect();
+    if pattern.is_empty() {
+      return Some(Match::default());
+    }
+
+    let mut next = 0;
+    for &c in &folded {
+      if next < pattern.len() && c == pattern[next] {
+        next += 1;
+      }
+    }
+    if next < pattern.len() {
+      return None;
+    }
+
+    let n = chars.len();
+    let m = pattern.len();
+    let bonuses: Vec<i64> = (0..n).map(|i| bonus(&chars, i)).collect();
+
+    let mut scores = vec![vec![None::<i64>; n]; m];
+    let mut back = vec![vec![0usize; n]; m];
+    let mut run_bonuses = vec![vec![0i64; n]; m];
+
+    for i in 0..n {
+      if folded[i] == pattern[0] {
+        scores[0][i] = Some(SCORE_MATCH + bonuses[i] * 2);
+        run_bonuses[0][i] = bonuses[i];
+      }
+    }
+
+    for j in 1..m {
+      let mut best_gapped: Option<(i64, usize)> = None;
+      for i in 1..n {
+        if i >= 2
+          && let Some(score) = scores[j - 1][i - 2]
+        {
+          let weighted = score + SCORE_GAP_EXTENSION * (i as i64 - 2);
+          if best_gapped.is_none_or(|(best, _)| weighted > best) {
+            best_gapped = Some((weighted, i - 2));
+          }
+        }
+
+        if folded[i] != pattern[j] {
+          continue;
+        }
+
+        let mut candidate: Option<(i64, usize, i64)> = None;
+        if let Some(score) = scores[j - 1][i - 1] {
+          let run_bonus = run_bonuses[j - 1][i - 1].max(bonuses[i]);
+          candidate = Some((score + run_bonus.max(BONUS_CONSECUTIVE), i - 1, run_bonus));
+        }
+        if let Some((weighted, k)) = best_gapped {
+          let score = weighted - SCORE_GAP_START - SCORE_GAP_EXTENSION * (i as i64 - 2) + bonuses[i];
+          if candidate.is_none_or(|(best, _, _)| score > best) {
+            candidate = Some((score, k, bonuses[i]));
+          }
+        }
+
+        if let Some((score, k, run_bonus)) = candidate {
+          scores[j][i] = Some(score + SCORE_MATCH);
+          back[j][i] = k;
+          run_bonuses[j][i] = run_bonus;
+        }
+      }
+    }
+
+    let (mut index, score) = scores[m - 1]
+      .iter()
+      .enumerate()
+      .filter_map(|(i, score)| score.map(|score| (i, score)))
+      .max_by_key(|&(i, score)| (score, std::cmp::Reverse(i)))?;
+
+    let mut positions = vec![0; m];
+    for j in (0..m).rev() {
+      positions[j] = index;
+      if j > 0 {
+        index = back[j][index];
+      }
+    }
+
+    Some(Match { score, positions })
+  }
+
+  pub fn keyword_match(path: &str, keywords: &[String]) -> bool {
+    let haystack = path.to_lowercase();
+    let mut from = 0;
+    for keyword in keywords {
+      let keyword = keyword.to_lowercase();
+      match haystack[from..].find(&keyword) {
+        Some(index) => from += index + keyword.len(),
+        None => return false,
+      }
+    }
+
+    match keywords.last() {
+      Some(last) => {
+        let name = haystack.rsplit('/').next().unwrap_or_default();
+        name.contains(&last.to_lowercase())
+      }
+      None => true,
+    }
+  }
+
+  pub fn expand_home(pattern: &str) -> String {
+    match pattern.strip_prefix('~') {
+      Some(rest) => format!("{}{}", std::env::var("HOME").unwrap_or_default(), rest),
+      None => pattern.to_string(),
+    }
+  }
+
+  pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_match_from(&pattern, &text)
+  }
+
+  fn glob_match_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+      None => text.is_empty(),
+      Some('*') if pattern.get(1) == Some(&'*') => {
+        let rest = &pattern[2..];
+        (0..=text.len()).any(|skip| glob_match_from(rest, &text[skip..]))
+      }
+      Some('*') => {
+        let rest = &pattern[1..];
+        for skip in 0..=text.len() {
+          if glob_match_from(rest, &text[skip..]) {
+            return true;
+          }
+          if text.get(skip) == Some(&'/') {
+            break;
+          }
+        }
+        false
+      }
+      Some('?') => matches!(text.first(), Some(&c) if c != '/') && glob_match_from(&pattern[1..], &text[1..]),
+      Some(&c) => text.first() == Some(&c) && glob_match_from(&pattern[1..], &text[1..]),
+    }
+  }
+
+  pub fn combined_score(match_score: i64, frecency: u64) -> i64 {
+    match_score * 2 + (frecency + 1).ilog2() as i64 * 4
+  }
+
+  #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+  enum TermKind {
+    Fuzzy,
+    Exact,
+    Prefix,
+    Suffix,
+    Equal,
+  }
+
+  #[derive(Clone, Debug)]
+  struct Term {
+    kind: TermKind,
+    text: String,
+    negated: bool,
+  }
+
+  #[derive(Clone, Debug, Default)]
+  pub struct Query {
+    groups: Vec<Vec<Term>>,
+  }
+
+  impl Term {
+    fn parse(token: &str, mode: MatchMode) -> Option<Self> {
+      let mut text = token;
+      let mut negated = false;
+      let mut kind = match mode {
+        MatchMode::Fuzzy => TermKind::Fuzzy,
+        MatchMode::Exact => TermKind::Exact,
+      };
+
+      if let Some(rest) = text.strip_prefix('!') {
+        negated = true;
+        kind = TermKind::Exact;
+        text = rest;
+      }
+
+      if let Some(rest) = text.strip_prefix('\'') {
+        kind = if kind == TermKind::Exact && !negated {
+          TermKind::Fuzzy
+        } else {
+          TermKind::Exact
+        };
+        text = rest;
+      }
+
+      if let Some(rest) = text.strip_prefix('^') {
+        kind = TermKind::Prefix;
+        text = rest;
+      }
+
+      if let Some(rest) = text.strip_suffix('$') {
+        kind = if kind == TermKind::Prefix {
+          TermKind::Equal
+        } else {
+          TermKind::Suffix
+        };
+        text = rest;
+      }
+
+      if text.is_empty() {
+        return None;
+      }
+
+      Some(Term {
+        kind,
+        text: text.to_string(),
+        negated,
+      })
+    }
+
+    fn matches(&self, haystack: &str) -> Option<Match> {
+      match self.kind {
+        TermKind::Fuzzy => fuzzy_match(haystack, &self.text),
+        TermKind::Exact => exact_match(haystack, &self.text),
+        TermKind::Prefix => anchored_match(haystack, &self.text, Anchor::Start),
+        TermKind::Suffix => anchored_match(haystack, &self.text, Anchor::End),
+        TermKind::Equal => {
+          if haystack.chars().count() == self.text.chars().count() {
+            anchored_match(haystack, &self.text, Anchor::Start)
+          } else {
+            None
+          }
+        }
+      }
+    }
+  }
+
+  enum Anchor {
+    Start,
+    End,
+  }
+
+  fn anchored_match(haystack: &str, needle: &str, anchor: Anchor) -> Option<Match> {
+    let chars: Vec<char> = haystack.chars().collect();
+    let pattern: Vec<char> = needle.chars().map(fold).collect();
+    if pattern.len() > chars.len() {
+      return None;
+    }
+
+    let start = match anchor {
+      Anchor::Start => 0,
+      Anchor::End => chars.len() - pattern.len(),
+    };
+    let found = pattern
+      .iter()
+      .enumerate()
+      .all(|(offset, &c)| fold(chars[start + offset]) == c);
+    if !found {
+      return None;
+    }
+
+    Some(Match {
+      score: run_score(&chars, start, pattern.len()),
+      positions: (start..start + pattern.len()).collect(),
+    })
+  }
+
+  fn split_tokens(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+      if c == '\\' && chars.peek() == Some(&' ') {
+        current.push(' ');
+        chars.next();
+      } else if c == ' ' {
+        if !current.is_empty() {
+          tokens.push(std::mem::take(&mut current));
+        }
+      } else {
+        current.push(c);
+      }
+    }
+    if !current.is_empty() {
+      tokens.push(current);
+    }
+
+    tokens
+  }
+
+  impl Query {
+    pub fn parse(input: &str, mode: MatchMode) -> Self {
+      let mut groups: Vec<Vec<Term>> = Vec::new();
+      let mut join_previous = false;
+
+      for token in split_tokens(input) {
+        if token == "|" {
+          join_previous = !groups.is_empty();
+          continue;
+        }
+        let Some(term) = Term::parse(&token, mode) else {
+          continue;
+        };
+        match groups.last_mut() {
+          Some(group) if join_previous => group.push(term),
+          _ => groups.push(vec![term]),
+        }
+        join_previous = false;
+      }
+
+      Query { groups }
+    }
+
+    pub fn is_empty(&self) -> bool {
+      self.groups.is_empty()
+    }
+
+    pub fn matches(&self, haystack: &str) -> Option<Match> {
+      let mut result = Match::default();
+
+      for group in &self.groups {
+        let mut best: Option<Match> = None;
+        for term in group {
+          let found = term.matches(haystack);
+          let found = if term.negated {
+            match found {
+              Some(_) => None,
+              None => Some(Match::default()),
+            }
+          } else {
+            found
+          };
+          if let Some(found) = found
+            && best.as_ref().is_none_or(|current| found.score > current.score)
+          {
+            best = Some(found);
+          }
+        }
+
+        let best = best?;
+        result.score += best.score;
+        result.positions.extend(best.positions);
+      }
+
+      result.positions.sort_unstable();
+      result.positions.dedup();
+      Some(result)
+    }
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+
+    fn score(haystack: &str, needle: &str) -> i64 {
+      fuzzy_match(haystack, needle).unwrap().score
+    }
+
+    fn positions(haystack: &str, needle: &str) -> Vec<usize> {
+      fuzzy_match(haystack, needle).unwrap().positions
+    }
+
+    #[test]
+    fn contiguous_matches_beat_scattered_ones() {
+      assert!(score("abc", "abc") > score("a/b/c", "abc"));
+      assert!(score("~/proj/src", "src") > score("~/s/r/c", "src"));
+      assert!(score("~/work/api", "api") > score("~/a/p/i", "api"));
+      assert!(score("abcdef", "abc") > score("axbxcx", "abc"));
+    }
+
+    #[test]
+    fn boundaries_beat_the_middle_of_a_word() {
+      assert!(score("foo/bar", "b") > score("foobar", "b"));
+      assert!(score("foo_bar", "b") > score("foobar", "b"));
+      assert!(score("fooBar", "b") > score("foobar", "b"));
+      assert!(score("bar", "b") > score("foo/bar", "b"));
+    }
+
+    #[test]
+    fn positions_follow_the_best_alignment() {
+      assert_eq!(positions("~/proj/src", "src"), vec![7, 8, 9]);
+      assert_eq!(positions("a_b_c", "abc"), vec![0, 2, 4]);
+      assert_eq!(positions("src/main/src", "src"), vec![0, 1, 2]);
+      assert_eq!(positions("xsrcx/src", "src"), vec![6, 7, 8]);
+      assert_eq!(positions("read_me", "rm"), vec![0, 5]);
+    }
+
+    #[test]
+    fn matching_ignores_case_and_requires_every_character() {
+      assert_eq!(positions("ReadMe", "readme"), vec![0, 1, 2, 3, 4, 5]);
+      assert!(fuzzy_match("abc", "abd").is_none());
+      assert!(fuzzy_match("ab", "abc").is_none());
+      assert!(fuzzy_match("cba", "abc").is_none());
+      assert_eq!(fuzzy_match("abc", "").unwrap().positions, Vec::<usize>::new());
+    }
+
+    #[test]
+    fn exact_matches_prefer_boundaries() {
+      let found = exact_match("testing/test", "test").unwrap();
+      assert_eq!(found.positions, vec![0, 1, 2, 3]);
+      assert!(exact_match("a/src", "src").unwrap().score > exact_match("asrc", "src").unwrap().score);
+      assert!(exact_match("s/r/c", "src").is_none());
+    }
+
+    fn query_matches(query: &str, mode: MatchMode, haystack: &str) -> bool {
+      Query::parse(query, mode).matches(haystack).is_some()
+    }
+
+    #[test]
+    fn negated_terms_exclude_exact_substrings() {
+      assert!(query_matches("!test", MatchMode::Fuzzy, "src/main"));
+      assert!(!query_matches("!test", MatchMode::Fuzzy, "src/tests"));
+      assert!(query_matches("!tst", MatchMode::Fuzzy, "src/test"));
+      assert!(query_matches("src !test", MatchMode::Fuzzy, "proj/src"));
+      assert!(!query_matches("src !test", MatchMode::Fuzzy, "proj/src/test"));
+    }
+
+    #[test]
+    fn quoted_terms_flip_the_match_mode() {
+      assert!(query_matches("sr", MatchMode::Fuzzy, "s_r"));
+      assert!(!query_matches("'sr", MatchMode::Fuzzy, "s_r"));
+      assert!(query_matches("'sr", MatchMode::Fuzzy, "src"));
+      assert!(!query_matches("mdr", MatchMode::Exact, "my_dir"));
+      assert!(query_matches("'mdr", MatchMode::Exact, "my_dir"));
+      assert!(!query_matches("!'dir", MatchMode::Exact, "my_dir"));
+    }
+
+    #[test]
+    fn anchors_match_the_start_and_end() {
+      assert!(query_matches("^src", MatchMode::Fuzzy, "src/main"));
+      assert!(!query_matches("^src", MatchMode::Fuzzy, "proj/src"));
+      assert!(query_matches("rs$", MatchMode::Fuzzy, "main.rs"));
+      assert!(!query_matches("rs$", MatchMode::Fuzzy, "rs.main"));
+      assert!(query_matches("^abc$", MatchMode::Fuzzy, "ABC"));
+      assert!(!query_matches("^abc$", MatchMode::Fuzzy, "abcd"));
+      assert!(!query_matches("^abc$", MatchMode::Fuzzy, "xabc"));
+      assert_eq!(Query::parse("rs$", MatchMode::Fuzzy).matches("main.rs").unwrap().positions, vec![5, 6]);
+    }
+
+    #[test]
+    fn bars_join_alternatives() {
+      assert!(query_matches("foo | bar", MatchMode::Fuzzy, "x/bar"));
+      assert!(query_matches("foo | bar", MatchMode::Fuzzy, "x/foo"));
+      assert!(!query_matches("foo | bar", MatchMode::Fuzzy, "x/baz"));
+      assert!(query_matches("src foo | bar", MatchMode::Fuzzy, "src/bar"));
+      assert!(!query_matches("src foo | bar", MatchMode::Fuzzy, "lib/bar"));
+      assert!(query_matches("| foo", MatchMode::Fuzzy, "foo"));
+    }
+
+    #[test]
+    fn escaped_spaces_stay_in_one_term() {
+      assert_eq!(split_tokens("my\\ dir  src"), vec!["my dir", "src"]);
+      assert!(query_matches("my\\ dir", MatchMode::Exact, "x/my dir"));
+      assert!(!query_matches("my\\ dir", MatchMode::Exact, "x/my/dir"));
+    }
+
+    #[test]
+    fn terms_combine_their_positions() {
+      let found = Query::parse("a c", MatchMode::Fuzzy).matches("abc").unwrap();
+      assert_eq!(found.positions, vec![0, 2]);
+      assert!(Query::parse("", MatchMode::Fuzzy).is_empty());
+      assert!(Query::parse("! ' ^ $", MatchMode::Fuzzy).is_empty());
+    }
+  }
@@ -0,0 +1,438 @@
+use std::fs::{self, File, Metadata};
+  use std::io::{self, Read};
+  use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+  use std::path::{Path, PathBuf};
+  use std::sync::OnceLock;
+
+  use ratatui::style::{Color, Style};
+  use ratatui::text::{Line, Span};
+  use syntect::easy::HighlightLines;
+  use syntect::highlighting::{Theme, ThemeSet};
+  use syntect::parsing::{SyntaxReference, SyntaxSet};
+  use syntect::util::LinesWithEndings;
+
+  use crate::config;
+  use crate::get_sorted_entries;
+  use crate::history::History;
+  use crate::previewer::{JobStatus, PreviewJob, Previewers};
+
+  const MAX_TEXT_BYTES: u64 = 64 * 1024;
+  const MAX_HIGHLIGHT_LINES: usize = 1000;
+  const MAX_HIGHLIGHT_LINE_LENGTH: usize = 1000;
+  const THEME: &str = "base16-ocean.dark";
+  const SNIFF_BYTES: usize = 8 * 1024;
+  const MAX_HEX_BYTES: usize = 4 * 1024;
+  const HEX_ROW_BYTES: usize = 16;
+
+  const MAGIC: &[(usize, &[u8], &str, &str)] = &[
+    (0, b"\x7fELF", "ELF executable", "application/x-executable"),
+    (0, b"\x89PNG\r\n\x1a\n", "PNG image", "image/png"),
+    (0, b"\xff\xd8\xff", "JPEG image", "image/jpeg"),
+    (0, b"GIF87a", "GIF image", "image/gif"),
+    (0, b"GIF89a", "GIF image", "image/gif"),
+    (0, b"%PDF-", "PDF document", "application/pdf"),
+    (0, b"PK\x03\x04", "Zip archive", "application/zip"),
+    (0, b"PK\x05\x06", "Zip archive (empty)", "application/zip"),
+    (0, b"\x1f\x8b", "gzip compressed data", "application/gzip"),
+    (0, b"BZh", "bzip2 compressed data", "application/x-bzip2"),
+    (0, b"\xfd7zXZ\x00", "xz compressed data", "application/x-xz"),
+    (0, b"\x28\xb5\x2f\xfd", "Zstandard compressed data", "application/zstd"),
+    (0, b"7z\xbc\xaf\x27\x1c", "7-Zip archive", "application/x-7z-compressed"),
+    (0, b"\xca\xfe\xba\xbe", "Java class / Mach-O universal binary", "application/java-vm"),
+    (0, b"\xcf\xfa\xed\xfe", "Mach-O executable", "application/x-mach-binary"),
+    (0, b"\xce\xfa\xed\xfe", "Mach-O executable", "application/x-mach-binary"),
+    (0, b"MZ", "DOS/Windows executable", "application/x-dosexec"),
+    (0, b"\x00asm", "WebAssembly module", "application/wasm"),
+    (0, b"SQLite format 3\x00", "SQLite database", "application/vnd.sqlite3"),
+    (0, b"RIFF", "RIFF container (WAV/AVI/WebP)", "application/x-riff"),
+    (0, b"OggS", "Ogg media", "audio/ogg"),
+    (0, b"fLaC", "FLAC audio", "audio/flac"),
+    (0, b"ID3", "MP3 audio", "audio/mpeg"),
+    (257, b"ustar", "tar archive", "application/x-tar"),
+  ];
+
+  fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
+  }
+
+  fn theme() -> &'static Theme {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    &THEME_SET.get_or_init(ThemeSet::load_defaults).themes[THEME]
+  }
+
+  pub struct Preview {
+    pub path: PathBuf,
+    pub lines: Vec<Line<'static>>,
+    job: Option<PreviewJob>,
+  }
+
+  impl Preview {
+    pub fn build(path: &Path, history: &History, previewers: &Previewers) -> Self {
+      if let Some(command) = previewers.find(path) {
+        let (lines, job) = match PreviewJob::spawn(command, path) {
+          Ok(job) => (vec![message(&format!("running {}…", command))], Some(job)),
+          Err(error) => (vec![message(&format!("{}: {}", command, error))], None),
+        };
+        return Preview {
+          path: path.to_path_buf(),
+          lines,
+          job,
+        };
+      }
+
+      let lines = if path.is_dir() {
+        directory_lines(path, history)
+      } else {
+        file_lines(path)
+      };
+      let lines = lines.unwrap_or_else(|error| vec![message(&error.to_string())]);
+
+      Preview {
+        path: path.to_path_buf(),
+        lines,
+        job: None,
+      }
+    }
+
+    pub fn is_running(&self) -> bool {
+      self.job.is_some()
+    }
+
+    pub fn poll(&mut self) {
+      let Some(job) = self.job.as_mut() else {
+        return;
+      };
+      match job.poll() {
+        JobStatus::Running => return,
+        JobStatus::Finished(lines) if lines.is_empty() => self.lines = vec![message("(no output)")],
+        JobStatus::Finished(lines) => self.lines = lines,
+        JobStatus::Failed(error) => self.lines = vec![message(&error)],
+      }
+      self.job = None;
+    }
+  }
+
+  fn message(text: &str) -> Line<'static> {
+    Line::from(Span::styled(text.to_string(), Style::default().fg(config::get().colors.message)))
+  }
+
+  fn directory_lines(path: &Path, history: &History) -> io::Result<Vec<Line<'static>>> {
+    let mut entries = get_sorted_entries(path, history, false)?;
+    entries.extend(get_sorted_entries(path, history, true)?);
+
+    if entries.is_empty() {
+      return Ok(vec![message("(empty directory)")]);
+    }
+
+    Ok(entries
+      .into_iter()
+      .map(|(child, _, is_dir)| {
+        let name = child.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if is_dir {
+          Line::from(Span::styled(format!("{}/", name), Style::default().fg(config::get().colors.directory)))
+        } else {
+          Line::from(Span::styled(name, Style::default().fg(config::get().colors.file)))
+        }
+      })
+      .collect())
+  }
+
+  fn detect_syntax(path: &Path, text: &str) -> Option<&'static SyntaxReference> {
+    let syntaxes = syntax_set();
+    if let Ok(Some(syntax)) = syntaxes.find_syntax_for_file(path) {
+      return Some(syntax);
+    }
+    let first_line = text.lines().next()?;
+    syntaxes.find_syntax_by_first_line(first_line)
+  }
+
+  fn convert_style(style: syntect::highlighting::Style) -> Style {
+    let color = style.foreground;
+    Style::default().fg(Color::Rgb(color.r, color.g, color.b))
+  }
+
+  fn is_binary(buffer: &[u8]) -> bool {
+    let sample = &buffer[..buffer.len().min(SNIFF_BYTES)];
+    if sample.contains(&0) {
+      return true;
+    }
+    if let Err(error) = std::str::from_utf8(sample)
+      && error.error_len().is_some()
+    {
+      return true;
+    }
+    let control = sample
+      .iter()
+      .filter(|&&byte| byte < 0x20 && !matches!(byte, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b))
+      .count();
+    control * 10 > sample.len()
+  }
+
+  fn detect_type(buffer: &[u8]) -> (&'static str, &'static str) {
+    MAGIC
+      .iter()
+      .find(|(offset, magic, _, _)| buffer.get(*offset..offset + magic.len()) == Some(*magic))
+      .map_or(("data", "application/octet-stream"), |(_, _, name, mime)| (*name, *mime))
+  }
+
+  fn special_kind(metadata: &Metadata) -> Option<(&'static str, &'static str)> {
+    let file_type = metadata.file_type();
+    if file_type.is_file() || file_type.is_dir() {
+      None
+    } else if file_type.is_fifo() {
+      Some(("fifo", "inode/fifo"))
+    } else if file_type.is_socket() {
+      Some(("socket", "inode/socket"))
+    } else if file_type.is_char_device() {
+      Some(("character device", "inode/chardevice"))
+    } else if file_type.is_block_device() {
+      Some(("block device", "inode/blockdevice"))
+    } else {
+      Some(("special file", "inode/x-unknown"))
+    }
+  }
+
+  fn sniff(path: &Path) -> io::Result<Vec<u8>> {
+    if let Some((kind, _)) = special_kind(&fs::metadata(path)?) {
+      return Err(io::Error::other(kind));
+    }
+    let mut buffer = Vec::new();
+    File::open(path)?.take(SNIFF_BYTES as u64).read_to_end(&mut buffer)?;
+    Ok(buffer)
+  }
+
+  pub fn detect_mime(path: &Path) -> &'static str {
+    let Ok(metadata) = fs::metadata(path) else {
+      return "application/octet-stream";
+    };
+    if metadata.is_dir() {
+      return "inode/directory";
+    }
+    if let Some((_, mime)) = special_kind(&metadata) {
+      return mime;
+    }
+    match sniff(path) {
+      Ok(buffer) if buffer.is_empty() => "inode/x-empty",
+      Ok(buffer) if is_binary(&buffer) => detect_type(&buffer).1,
+      Ok(_) => "text/plain",
+      Err(_) => "application/octet-stream",
+    }
+  }
+
+  fn format_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+      value /= 1024.0;
+      unit += 1;
+    }
+    if unit == 0 {
+      format!("{} B", size)
+    } else {
+      format!("{:.1} {}", value, UNITS[unit])
+    }
+  }
+
+  fn format_permissions(metadata: &Metadata) -> String {
+    let mode = metadata.permissions().mode();
+    let mut text = String::from("-");
+    for shift in [6, 3, 0] {
+      let bits = (mode >> shift) & 0o7;
+      text.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+      text.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+      text.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    format!("{} ({:o})", text, mode & 0o7777)
+  }
+
+  fn hex_lines(buffer: &[u8], metadata: &Metadata) -> Vec<Line<'static>> {
+    let colors = &config::get().colors;
+    let label = Style::default().fg(colors.preview_label);
+    let offset_style = Style::default().fg(colors.message);
+    let ascii_style = Style::default().fg(colors.preview_ascii);
+
+    let mut lines = vec![
+      Line::from(vec![Span::styled("Size:        ", label), Span::raw(format_size(metadata.len()))]),
+      Line::from(vec![Span::styled("Type:        ", label), Span::raw(detect_type(buffer).0)]),
+      Line::from(vec![Span::styled("Permissions: ", label), Span::raw(format_permissions(metadata))]),
+      Line::from(""),
+    ];
+
+    let shown = &buffer[..buffer.len().min(MAX_HEX_BYTES)];
+    for (row, chunk) in shown.chunks(HEX_ROW_BYTES).enumerate() {
+      let mut hex = String::new();
+      for index in 0..HEX_ROW_BYTES {
+        match chunk.get(index) {
+          Some(byte) => hex.push_str(&format!("{:02x} ", byte)),
+          None => hex.push_str("   "),
+        }
+        if index == HEX_ROW_BYTES / 2 - 1 {
+          hex.push(' ');
+        }
+      }
+      let ascii: String = chunk
+        .iter()
+        .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })
+        .collect();
+
+      lines.push(Line::from(vec![
+        Span::styled(format!("{:08x}  ", row * HEX_ROW_BYTES), offset_style),
+        Span::raw(hex),
+        Span::styled(format!("|{}|", ascii), ascii_style),
+      ]));
+    }
+
+    if metadata.len() > shown.len() as u64 {
+      lines.push(message(&format!(
+        "… showing the first {} of {}",
+        format_size(shown.len() as u64),
+        format_size(metadata.len())
+      )));
+    }
+
+    lines
+  }
+
+  fn file_lines(path: &Path) -> io::Result<Vec<Line<'static>>> {
+    let metadata = fs::metadata(path)?;
+    if let Some((kind, _)) = special_kind(&metadata) {
+      return Ok(vec![message(&format!("({})", kind))]);
+    }
+    let size = metadata.len();
+    let mut buffer = Vec::new();
+    File::open(path)?.take(MAX_TEXT_BYTES).read_to_end(&mut buffer)?;
+
+    if buffer.is_empty() {
+      return Ok(vec![message("(empty file)")]);
+    }
+
+    if is_binary(&buffer) {
+      return Ok(hex_lines(&buffer, &metadata));
+    }
+
+    let text = String::from_utf8_lossy(&buffer).replace('\t', "    ");
+    let mut highlighter = detect_syntax(path, &text).map(|syntax| HighlightLines::new(syntax, theme()));
+    let line_count = text.lines().count();
+    let gutter_width = line_count.to_string().len();
+    let gutter_style = Style::default().fg(config::get().colors.message);
+
+    let mut lines: Vec<Line<'static>> = LinesWithEndings::from(&text)
+      .enumerate()
+      .map(|(index, line)| {
+        let mut spans = vec![Span::styled(format!("{:>width$} │ ", index + 1, width = gutter_width), gutter_style)];
+
+        let highlighted = match highlighter.as_mut() {
+          Some(highlighter) if index < MAX_HIGHLIGHT_LINES && line.len() <= MAX_HIGHLIGHT_LINE_LENGTH => {
+            highlighter.highlight_line(line, syntax_set()).ok()
+          }
+          _ => None,
+        };
+
+        match highlighted {
+          Some(regions) => {
+            for (style, piece) in regions {
+              let piece = piece.trim_end_matches(['\n', '\r']);
+              if !piece.is_empty() {
+                spans.push(Span::styled(piece.to_string(), convert_style(style)));
+              }
+            }
+          }
+          None => {
+            highlighter = None;
+            spans.push(Span::raw(line.trim_end_matches(['\n', '\r']).to_string()));
+          }
+        }
+
+        Line::from(spans)
+      })
+      .collect();
+
+    if size > MAX_TEXT_BYTES {
+      lines.push(message(&format!(
+        "… showing the first {} KiB of {} KiB",
+        MAX_TEXT_BYTES / 1024,
+        size.div_ceil(1024)
+      )));
+    }
+
+    Ok(lines)
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+
+    use std::process::Command;
+
+    #[test]
+    fn special_files_are_not_opened() {
+      let dir = std::env::temp_dir().join(format!("ff-preview-fifo-{}", std::process::id()));
+      let _ = fs::remove_dir_all(&dir);
+      fs::create_dir_all(&dir).unwrap();
+      let fifo = dir.join("pipe");
+      assert!(Command::new("mkfifo").arg(&fifo).status().unwrap().success());
+
+      let lines = file_lines(&fifo).unwrap();
+      assert_eq!(lines.len(), 1);
+      assert_eq!(lines[0].to_string(), "(fifo)");
+      assert_eq!(detect_mime(&fifo), "inode/fifo");
+      assert_eq!(sniff(&fifo).unwrap_err().to_string(), "fifo");
+      assert_eq!(detect_mime(Path::new("/dev/null")), "inode/chardevice");
+
+      fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn binary_detection_rules() {
+      assert!(!is_binary(b"plain text\n\twith tabs\r\n"));
+      assert!(!is_binary("caf\u{e9} \u{1b}[1mbold\u{1b}[0m\x0c".as_bytes()));
+      assert!(is_binary(b"text with a \0 in it"));
+      assert!(is_binary(b"latin-1 caf\xe9 text"));
+      assert!(!is_binary("truncated \u{e9}".as_bytes().split_last().unwrap().1));
+      assert!(is_binary(b"\x01\x02ab"));
+      assert!(!is_binary(b"\x01bcdefghijklmnopqrst"));
+    }
+
+    #[test]
+    fn magic_bytes_name_the_type() {
+      assert_eq!(detect_type(b"\x7fELF\x02\x01\x01"), ("ELF executable", "application/x-executable"));
+      assert_eq!(detect_type(b"\x89PNG\r\n\x1a\n\0\0"), ("PNG image", "image/png"));
+      assert_eq!(detect_type(b"\x1f\x8b\x08\0"), ("gzip compressed data", "application/gzip"));
+
+      let mut tar = vec![0; 512];
+      tar[..8].copy_from_slice(b"file.txt");
+      tar[257..263].copy_from_slice(b"ustar\0");
+      assert_eq!(detect_type(&tar), ("tar archive", "application/x-tar"));
+      assert_eq!(detect_type(&tar[..260]), ("data", "application/octet-stream"));
+      assert_eq!(detect_type(b"\0\x01\x02"), ("data", "application/octet-stream"));
+    }
+
+    #[test]
+    fn hexdump_rows_and_header() {
+      let dir = std::env::temp_dir().join(format!("ff-preview-hex-{}", std::process::id()));
+      let _ = fs::remove_dir_all(&dir);
+      fs::create_dir_all(&dir).unwrap();
+      let file = dir.join("blob");
+      let bytes: Vec<u8> = (0x3c..0x4e).chain([0, b' ', 0xff]).collect();
+      fs::write(&file, &bytes).unwrap();
+      fs::set_permissions(&file, fs::Permissions::from_mode(0o640)).unwrap();
+      let metadata = fs::metadata(&file).unwrap();
+
+      assert_eq!(format_permissions(&metadata), "-rw-r----- (640)");
+      let lines: Vec<String> = hex_lines(&bytes, &metadata).iter().map(ToString::to_string).collect();
+      assert_eq!(lines[0], "Size:        21 B");
+      assert_eq!(lines[1], "Type:        data");
+      assert_eq!(lines[2], "Permissions: -rw-r----- (640)");
+      assert_eq!(lines[3], "");
+      assert_eq!(lines[4], "00000000  3c 3d 3e 3f 40 41 42 43  44 45 46 47 48 49 4a 4b |<=>?@ABCDEFGHIJK|");
+      assert_eq!(lines[5], "00000010  4c 4d 00 20 ff                                   |LM. .|");
+      assert_eq!(lines.len(), 6);
+
+      fs::set_permissions(&file, fs::Permissions::from_mode(0o4755)).unwrap();
+      assert_eq!(format_permissions(&fs::metadata(&file).unwrap()), "-rwxr-xr-x (4755)");
+
+      fs::remove_dir_all(&dir).unwrap();
+    }
+  }
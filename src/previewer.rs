@@ -0,0 +1,270 @@
+use std::io::{self, Read};
+  use std::os::unix::process::CommandExt;
+  use std::path::Path;
+  use std::process::{Child, Command, Stdio};
+  use std::sync::mpsc::{self, Receiver, TryRecvError};
+  use std::thread;
+  use std::time::{Duration, Instant};
+
+  use ansi_to_tui::IntoText;
+  use ratatui::text::Line;
+
+  use crate::config;
+  use crate::preview::detect_mime;
+
+  const TIMEOUT: Duration = Duration::from_secs(3);
+  const MAX_OUTPUT_BYTES: u64 = 256 * 1024;
+
+  #[derive(Default)]
+  pub struct Previewers {
+    rules: Vec<(String, String)>,
+  }
+
+  impl Previewers {
+    pub fn load() -> Self {
+      let rules = config::get()
+        .previewers
+        .iter()
+        .map(|(key, command)| (key.trim_start_matches('.').to_lowercase(), command.clone()))
+        .collect();
+
+      Previewers { rules }
+    }
+
+    pub fn find(&self, path: &Path) -> Option<&str> {
+      let name = path.file_name()?.to_string_lossy().to_lowercase();
+
+      let by_extension = self.rules
+        .iter()
+        .filter(|(key, _)| !key.contains('/') && name.ends_with(&format!(".{}", key)))
+        .max_by_key(|(key, _)| key.len());
+      if let Some((_, command)) = by_extension {
+        return Some(command);
+      }
+
+      let mime = detect_mime(path);
+      let (family, _) = mime.split_once('/').unwrap_or((mime, ""));
+      self.rules
+        .iter()
+        .find(|(key, _)| *key == mime)
+        .or_else(|| self.rules.iter().find(|(key, _)| *key == format!("{}/*", family)))
+        .map(|(_, command)| command.as_str())
+    }
+  }
+
+  pub enum JobStatus {
+    Running,
+    Finished(Vec<Line<'static>>),
+    Failed(String),
+  }
+
+  pub struct PreviewJob {
+    child: Child,
+    receiver: Receiver<io::Result<Vec<u8>>>,
+    started: Instant,
+  }
+
+  impl PreviewJob {
+    pub fn spawn(command: &str, path: &Path) -> io::Result<Self> {
+      let script = if command.contains("{}") {
+        command.replace("{}", "\"$1\"")
+      } else {
+        format!("{} \"$1\"", command)
+      };
+
+      let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(script)
+        .arg("ff")
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+
+      let mut stdout = child.stdout.take().ok_or_else(|| io::Error::other("previewer has no stdout"))?;
+      let (sender, receiver) = mpsc::channel();
+      thread::spawn(move || {
+        let mut output = Vec::new();
+        let result = (&mut stdout).take(MAX_OUTPUT_BYTES).read_to_end(&mut output).map(|_| output);
+        let _ = sender.send(result);
+      });
+
+      Ok(PreviewJob {
+        child,
+        receiver,
+        started: Instant::now(),
+      })
+    }
+
+    pub fn poll(&mut self) -> JobStatus {
+      match self.receiver.try_recv() {
+        Ok(Ok(output)) => {
+          if output.len() as u64 >= MAX_OUTPUT_BYTES {
+            self.kill();
+          }
+          let status = self.child.wait();
+          if output.is_empty() {
+            return match status {
+              Ok(status) if !status.success() => JobStatus::Failed(format!("previewer failed ({})", status)),
+              _ => JobStatus::Finished(Vec::new()),
+            };
+          }
+          match output.into_text() {
+            Ok(text) => JobStatus::Finished(text.lines),
+            Err(error) => JobStatus::Failed(format!("previewer output: {}", error)),
+          }
+        }
+        Ok(Err(error)) => {
+          self.cancel();
+          JobStatus::Failed(format!("previewer output: {}", error))
+        }
+        Err(TryRecvError::Disconnected) => {
+          self.cancel();
+          JobStatus::Failed(String::from("previewer exited unexpectedly"))
+        }
+        Err(TryRecvError::Empty) if self.started.elapsed() > TIMEOUT => {
+          self.cancel();
+          JobStatus::Failed(format!("previewer timed out after {}s", TIMEOUT.as_secs()))
+        }
+        Err(TryRecvError::Empty) => JobStatus::Running,
+      }
+    }
+
+    fn kill(&mut self) {
+      unsafe {
+        libc::kill(-(self.child.id() as libc::pid_t), libc::SIGKILL);
+      }
+    }
+
+    pub fn cancel(&mut self) {
+      self.kill();
+      let _ = self.child.wait();
+    }
+  }
+
+  impl Drop for PreviewJob {
+    fn drop(&mut self) {
+      if let Ok(None) = self.child.try_wait() {
+        self.cancel();
+      }
+    }
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+      let dir = std::env::temp_dir().join(format!("ff-previewer-{}-{}", name, std::process::id()));
+      let _ = fs::remove_dir_all(&dir);
+      fs::create_dir_all(&dir).unwrap();
+      dir
+    }
+
+    fn wait(job: &mut PreviewJob, limit: Duration) -> JobStatus {
+      let started = Instant::now();
+      loop {
+        match job.poll() {
+          JobStatus::Running if started.elapsed() < limit => thread::sleep(Duration::from_millis(20)),
+          status => return status,
+        }
+      }
+    }
+
+    fn is_alive(pid: &str) -> bool {
+      fs::read_to_string(format!("/proc/{}/stat", pid))
+        .is_ok_and(|stat| stat.rsplit(") ").next().is_some_and(|rest| !rest.starts_with('Z')))
+    }
+
+    #[test]
+    fn find_prefers_the_longest_extension_then_the_mime_type() {
+      let dir = scratch_dir("find");
+      let previewers = Previewers {
+        rules: [
+          ("gz", "zcat"),
+          ("tar.gz", "tar tzf"),
+          ("rs", "bat"),
+          ("application/gzip", "gzip -l"),
+          ("image/*", "chafa"),
+        ]
+        .into_iter()
+        .map(|(key, command)| (key.to_string(), command.to_string()))
+        .collect(),
+      };
+      let gzip = b"\x1f\x8b\x08\x00\x00\x00\x00\x00";
+      let png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR";
+      for (name, content) in [
+        ("a.tar.gz", &gzip[..]),
+        ("b.gz", &gzip[..]),
+        ("archive", &gzip[..]),
+        ("picture", &png[..]),
+        ("MAIN.RS", &png[..]),
+        ("notes", b"just text\n"),
+      ] {
+        fs::write(dir.join(name), content).unwrap();
+      }
+
+      assert_eq!(previewers.find(&dir.join("a.tar.gz")), Some("tar tzf"));
+      assert_eq!(previewers.find(&dir.join("b.gz")), Some("zcat"));
+      assert_eq!(previewers.find(&dir.join("archive")), Some("gzip -l"));
+      assert_eq!(previewers.find(&dir.join("picture")), Some("chafa"));
+      assert_eq!(previewers.find(&dir.join("MAIN.RS")), Some("bat"));
+      assert_eq!(previewers.find(&dir.join("notes")), None);
+
+      fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn output_is_collected() {
+      let mut job = PreviewJob::spawn("printf 'preview of %s'", Path::new("x y")).unwrap();
+      match wait(&mut job, Duration::from_secs(2)) {
+        JobStatus::Finished(lines) => assert_eq!(lines[0].to_string(), "preview of x y"),
+        _ => panic!("previewer did not finish"),
+      }
+    }
+
+    #[test]
+    fn slow_previewers_time_out() {
+      let started = Instant::now();
+      let mut job = PreviewJob::spawn("sleep 10 #", Path::new("x")).unwrap();
+      match wait(&mut job, TIMEOUT + Duration::from_secs(2)) {
+        JobStatus::Failed(error) => assert_eq!(error, "previewer timed out after 3s"),
+        _ => panic!("previewer was not stopped"),
+      }
+      assert!(started.elapsed() >= TIMEOUT);
+      assert!(started.elapsed() < TIMEOUT + Duration::from_secs(2));
+    }
+
+    #[test]
+    fn dropping_the_job_kills_its_process_group() {
+      let dir = scratch_dir("drop");
+      let pid_file = dir.join("pid");
+      let job = PreviewJob::spawn("sleep 10 & echo $! > {}; wait", &pid_file).unwrap();
+
+      let started = Instant::now();
+      let pid = loop {
+        if let Ok(pid) = fs::read_to_string(&pid_file)
+          && pid.ends_with('\n')
+        {
+          break pid.trim().to_string();
+        }
+        assert!(started.elapsed() < Duration::from_secs(2), "previewer never started");
+        thread::sleep(Duration::from_millis(10));
+      };
+      assert!(is_alive(&pid));
+
+      drop(job);
+      let started = Instant::now();
+      while is_alive(&pid) {
+        assert!(started.elapsed() < Duration::from_secs(2), "sleep {} outlived the preview", pid);
+        thread::sleep(Duration::from_millis(10));
+      }
+
+      fs::remove_dir_all(&dir).unwrap();
+    }
+  }
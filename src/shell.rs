@@ -0,0 +1,238 @@
+use std::env;
+  use std::ffi::OsStr;
+  use std::fs::OpenOptions;
+  use std::io::{self, Write};
+  use std::os::unix::ffi::OsStrExt;
+  use std::path::{Path, PathBuf};
+
+  #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+  pub enum Shell {
+    Sh,
+    Bash,
+    Zsh,
+    Fish,
+  }
+
+  impl Shell {
+    pub fn from_name(name: &str) -> Option<Self> {
+      match name {
+        "bash" => Some(Shell::Bash),
+        "zsh" => Some(Shell::Zsh),
+        "fish" => Some(Shell::Fish),
+        _ => None,
+      }
+    }
+
+    pub fn from_path(path: &Path) -> Self {
+      match path.file_name().and_then(OsStr::to_str) {
+        Some("bash") => Shell::Bash,
+        Some("zsh") => Shell::Zsh,
+        Some("fish") => Shell::Fish,
+        _ => Shell::Sh,
+      }
+    }
+
+    pub fn name(self) -> &'static str {
+      match self {
+        Shell::Sh => "sh",
+        Shell::Bash => "bash",
+        Shell::Zsh => "zsh",
+        Shell::Fish => "fish",
+      }
+    }
+
+    pub fn init_script(self, hook: bool) -> String {
+      let (wrapper, hook_script) = match self {
+        Shell::Sh | Shell::Bash => (POSIX_INIT, BASH_HOOK),
+        Shell::Zsh => (POSIX_INIT, ZSH_HOOK),
+        Shell::Fish => (FISH_INIT, FISH_HOOK),
+      };
+
+      let mut script = wrapper
+        .replace("{shell}", self.name())
+        .replace("{hook}", if hook { "1" } else { "0" });
+      if hook {
+        script.push('\n');
+        script.push_str(hook_script);
+      }
+      script
+    }
+
+    pub fn quote(self, text: &[u8]) -> Vec<u8> {
+      match self {
+        Shell::Fish => quote_fish(text),
+        Shell::Sh | Shell::Bash | Shell::Zsh => quote_posix(text),
+      }
+    }
+
+    pub fn and_then(self) -> &'static str {
+      match self {
+        Shell::Fish => "; and ",
+        Shell::Sh | Shell::Bash | Shell::Zsh => " && ",
+      }
+    }
+
+    pub fn jump_script(self, leave_commands: &[String], dir: &Path, enter_commands: &[String]) -> Vec<u8> {
+      let mut script = Vec::new();
+      for leave_command in leave_commands {
+        script.extend_from_slice(leave_command.as_bytes());
+        script.push(b'\n');
+      }
+      script.extend_from_slice(b"cd ");
+      script.extend(self.quote(dir.as_os_str().as_bytes()));
+      for enter_command in enter_commands {
+        script.extend_from_slice(self.and_then().as_bytes());
+        script.extend_from_slice(enter_command.as_bytes());
+      }
+      script
+    }
+  }
+
+  fn quote_posix(text: &[u8]) -> Vec<u8> {
+    let mut quoted = vec![b'\''];
+    for &byte in text {
+      if byte == b'\'' {
+        quoted.extend_from_slice(b"'\\''");
+      } else {
+        quoted.push(byte);
+      }
+    }
+    quoted.push(b'\'');
+    quoted
+  }
+
+  fn quote_fish(text: &[u8]) -> Vec<u8> {
+    let mut quoted = Vec::new();
+    for chunk in text.utf8_chunks() {
+      if !chunk.valid().is_empty() {
+        quoted.push(b'\'');
+        for byte in chunk.valid().bytes() {
+          if byte == b'\\' || byte == b'\'' {
+            quoted.push(b'\\');
+          }
+          quoted.push(byte);
+        }
+        quoted.push(b'\'');
+      }
+      for byte in chunk.invalid() {
+        quoted.extend_from_slice(format!("\\X{:02x}", byte).as_bytes());
+      }
+    }
+    if quoted.is_empty() {
+      quoted.extend_from_slice(b"''");
+    }
+    quoted
+  }
+
+  const POSIX_INIT: &str = r#"ff() {
+  local __ff_out __ff_status
+  __ff_out="$(mktemp "${TMPDIR:-/tmp}/ff.XXXXXX")" || return
+  FF_SHELL={shell} FF_HOOK={hook} FF_OUT="$__ff_out" command ff "$@"
+  __ff_status=$?
+  if [ -s "$__ff_out" ]; then
+    . "$__ff_out"
+  fi
+  command rm -f -- "$__ff_out"
+  return $__ff_status
+}
+"#;
+
+  const FISH_INIT: &str = r#"function ff
+  set -l __ff_tmp /tmp
+  set -q TMPDIR; and set __ff_tmp $TMPDIR
+  set -l __ff_out (mktemp $__ff_tmp/ff.XXXXXX); or return
+  FF_SHELL=fish FF_HOOK={hook} FF_OUT=$__ff_out command ff $argv
+  set -l __ff_status $status
+  if test -s $__ff_out
+    source $__ff_out
+  end
+  command rm -f -- $__ff_out
+  return $__ff_status
+end
+"#;
+
+  const BASH_HOOK: &str = r#"__ff_hook() {
+  if [ "${__ff_pwd:-}" != "$PWD" ]; then
+    __ff_pwd="$PWD"
+    command ff add -- "$PWD"
+  fi
+}
+case ";${PROMPT_COMMAND:-};" in
+  *";__ff_hook;"*) ;;
+  *) PROMPT_COMMAND="__ff_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}" ;;
+esac
+"#;
+
+  const ZSH_HOOK: &str = r#"__ff_hook() {
+  command ff add -- "$PWD"
+}
+autoload -Uz add-zsh-hook
+add-zsh-hook chpwd __ff_hook
+"#;
+
+  const FISH_HOOK: &str = r#"function __ff_hook --on-variable PWD
+  command ff add -- $PWD
+end
+"#;
+
+  pub struct Handoff {
+    pub file: PathBuf,
+    pub shell: Shell,
+    pub hooked: bool,
+  }
+
+  pub fn detect_handoff() -> Option<Handoff> {
+    let file = PathBuf::from(env::var_os("FF_OUT")?);
+    let shell = env::var("FF_SHELL").ok().and_then(|name| Shell::from_name(&name)).unwrap_or(Shell::Sh);
+    let hooked = env::var("FF_HOOK").is_ok_and(|value| value == "1");
+    Some(Handoff { file, shell, hooked })
+  }
+
+  impl Handoff {
+    pub fn write(&self, leave_commands: &[String], dir: &Path, enter_commands: &[String]) -> io::Result<()> {
+      let mut script = self.shell.jump_script(leave_commands, dir, enter_commands);
+      script.push(b'\n');
+
+      let mut file = OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .open(&self.file)?;
+      file.write_all(&script)
+    }
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+
+    #[test]
+    fn fish_quoting_escapes_only_quotes_and_backslashes() {
+      let cases: &[(&str, &str)] = &[
+        ("it's", r"'it\'s'"),
+        ("x'; touch pwned; '", r"'x\'; touch pwned; \''"),
+        ("$(touch pwned)", "'$(touch pwned)'"),
+        ("`touch pwned`", "'`touch pwned`'"),
+        ("a\"b", "'a\"b'"),
+        ("semi;colon && touch pwned", "'semi;colon && touch pwned'"),
+        ("new\nline", "'new\nline'"),
+        ("tab\there", "'tab\there'"),
+        ("back\\slash", r"'back\\slash'"),
+        ("*?[glob]", "'*?[glob]'"),
+        ("~tilde", "'~tilde'"),
+        ("${HOME}", "'${HOME}'"),
+        ("-dash", "'-dash'"),
+        ("", "''"),
+      ];
+      for (text, expected) in cases {
+        assert_eq!(String::from_utf8(quote_fish(text.as_bytes())).unwrap(), *expected, "{:?}", text);
+      }
+    }
+
+    #[test]
+    fn fish_quoting_escapes_invalid_bytes_outside_quotes() {
+      assert_eq!(quote_fish(b"caf\xc3"), br"'caf'\Xc3");
+      assert_eq!(quote_fish(b"\xff\xfeok"), br"\Xff\Xfe'ok'");
+      assert_eq!(quote_fish(b"a'\x80b\\"), br"'a\''\X80'b\\'");
+    }
+  }
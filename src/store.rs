@@ -0,0 +1,40 @@
+use std::fs::{self, File, OpenOptions};
+  use std::io::{self, Write};
+  use std::path::{Path, PathBuf};
+  use std::process;
+
+  fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}{}", name, suffix))
+  }
+
+  pub fn with_lock<T>(path: &Path, update: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+    let lock_file = OpenOptions::new()
+      .write(true)
+      .create(true)
+      .truncate(false)
+      .open(sibling(path, ".lock"))?;
+    lock_file.lock()?;
+    let result = update();
+    lock_file.unlock()?;
+    result
+  }
+
+  pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let temp_path = sibling(path, &format!(".{}.tmp", process::id()));
+
+    let result = (|| {
+      let mut file = File::create(&temp_path)?;
+      file.write_all(contents)?;
+      file.sync_all()?;
+      if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(&temp_path, metadata.permissions())?;
+      }
+      fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+      let _ = fs::remove_file(&temp_path);
+    }
+    result
+  }
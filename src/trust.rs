@@ -0,0 +1,81 @@
+use std::fs;
+  use std::io;
+  use std::path::{Path, PathBuf};
+
+  use serde_json::{json, Map, Value};
+  use sha2::{Digest, Sha256};
+
+  use crate::config;
+  use crate::store;
+
+  #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+  pub enum Trust {
+    Allowed,
+    Denied,
+    New,
+    Changed,
+  }
+
+  fn get_trust_file_path() -> io::Result<PathBuf> {
+    Ok(config::get_state_dir()?.join("trust.json"))
+  }
+
+  fn hash(key: &str, command: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    hasher.update([0]);
+    hasher.update(command.as_bytes());
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+  }
+
+  fn read_entries(trust_file: &Path) -> io::Result<Map<String, Value>> {
+    let content = match fs::read_to_string(trust_file) {
+      Ok(content) => content,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Map::new()),
+      Err(e) => return Err(e),
+    };
+
+    match serde_json::from_str(&content) {
+      Ok(Value::Object(entries)) => Ok(entries),
+      _ => Ok(Map::new()),
+    }
+  }
+
+  fn update_entries(update: impl FnOnce(&mut Map<String, Value>)) -> io::Result<()> {
+    let trust_file = get_trust_file_path()?;
+
+    store::with_lock(&trust_file, || {
+      let mut entries = read_entries(&trust_file)?;
+      update(&mut entries);
+      let formatted = serde_json::to_string_pretty(&Value::Object(entries))?;
+      store::write_atomic(&trust_file, formatted.as_bytes())
+    })
+  }
+
+  pub fn check(key: &str, command: &str) -> io::Result<Trust> {
+    let entries = read_entries(&get_trust_file_path()?)?;
+    let Some(entry) = entries.get(key) else {
+      return Ok(Trust::New);
+    };
+
+    if entry.get("hash").and_then(Value::as_str) != Some(hash(key, command).as_str()) {
+      return Ok(Trust::Changed);
+    }
+    if entry.get("allowed").and_then(Value::as_bool) == Some(true) {
+      Ok(Trust::Allowed)
+    } else {
+      Ok(Trust::Denied)
+    }
+  }
+
+  pub fn set(key: &str, command: &str, allowed: bool) -> io::Result<()> {
+    update_entries(|entries| {
+      entries.insert(key.to_string(), json!({ "hash": hash(key, command), "allowed": allowed }));
+    })
+  }
+
+  pub fn forget(key: &str) -> io::Result<()> {
+    update_entries(|entries| {
+      entries.remove(key);
+    })
+  }
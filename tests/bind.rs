@@ -0,0 +1,226 @@
+use std::fs;
+  use std::path::Path;
+  use std::process::Output;
+
+  use serde_json::{json, Value};
+
+  mod common;
+  use common::{ff, scratch_dir};
+
+  fn bind(home: &Path, dir: &Path, binding: Value) {
+    bind_all(home, &[(dir, binding)]);
+  }
+
+  fn bind_all(home: &Path, bindings: &[(&Path, Value)]) {
+    let data_dir = home.join(".local/share/ff");
+    fs::create_dir_all(&data_dir).unwrap();
+    let scripts: serde_json::Map<String, Value> = bindings
+      .iter()
+      .map(|(dir, binding)| (dir.to_string_lossy().to_string(), binding.clone()))
+      .collect();
+    fs::write(data_dir.join("scripts.json"), Value::Object(scripts).to_string()).unwrap();
+  }
+
+  fn jump(home: &Path, dir: &Path) -> (String, Output) {
+    jump_from(home, home, dir)
+  }
+
+  fn jump_from(home: &Path, from: &Path, dir: &Path) -> (String, Output) {
+    let handoff = home.join("handoff");
+    let output = ff(home)
+      .current_dir(from)
+      .env("FF_SHELL", "bash")
+      .env("FF_HOOK", "0")
+      .env("FF_OUT", &handoff)
+      .arg(dir)
+      .output()
+      .unwrap();
+    assert!(output.status.success());
+    (fs::read_to_string(&handoff).unwrap(), output)
+  }
+
+  #[test]
+  fn bound_commands_run_only_once_allowed() {
+    let home = scratch_dir("bind-trust");
+    let target = home.join("project");
+    fs::create_dir(&target).unwrap();
+    let target = fs::canonicalize(&target).unwrap();
+    bind(&home, &target, json!("echo entered"));
+
+    let (script, output) = jump(&home, &target);
+    assert!(!script.contains("echo entered"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("not running the new command bound to"));
+    assert!(stderr.contains("echo entered"));
+
+    let status = ff(&home).args(["bind", "allow"]).arg(&target).status().unwrap();
+    assert!(status.success());
+    let (script, output) = jump(&home, &target);
+    assert!(script.contains("echo entered"));
+    assert!(output.stderr.is_empty());
+    assert!(home.join(".local/state/ff/trust.json").exists());
+    assert!(!home.join(".local/share/ff/trust.json").exists());
+
+    bind(&home, &target, json!("echo tampered"));
+    let (script, output) = jump(&home, &target);
+    assert!(!script.contains("echo tampered"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not running the changed command"));
+
+    let status = ff(&home).args(["bind", "deny"]).current_dir(&target).status().unwrap();
+    assert!(status.success());
+    let (script, output) = jump(&home, &target);
+    assert!(!script.contains("echo tampered"));
+    assert!(output.stderr.is_empty());
+
+    fs::remove_dir_all(&home).unwrap();
+  }
+
+  #[test]
+  fn allowing_an_unbound_directory_fails() {
+    let home = scratch_dir("bind-unbound");
+
+    let output = ff(&home).args(["bind", "allow"]).arg(&home).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no command is bound to"));
+
+    let output = ff(&home).args(["bind", "allow"]).arg(home.join("missing")).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Error: "));
+
+    let output = ff(&home).args(["bind", "trust"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    fs::remove_dir_all(&home).unwrap();
+  }
+
+  #[test]
+  fn leave_commands_run_when_a_jump_leaves_the_tree() {
+    let home = scratch_dir("bind-leave");
+    let project = fs::canonicalize(&home).unwrap().join("project");
+    let inside = project.join("src");
+    let sibling = project.join("docs");
+    let other = fs::canonicalize(&home).unwrap().join("other");
+    for dir in [&inside, &sibling, &other] {
+      fs::create_dir_all(dir).unwrap();
+    }
+    bind(&home, &project, json!({ "enter": "echo entered", "leave": "echo left" }));
+
+    let (script, output) = jump_from(&home, &inside, &other);
+    assert!(!script.contains("echo left"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("on leave: echo left"));
+
+    let status = ff(&home).args(["bind", "allow"]).arg(&project).status().unwrap();
+    assert!(status.success());
+
+    let (script, _) = jump_from(&home, &inside, &other);
+    assert_eq!(script, format!("echo left\ncd '{}'\n", other.display()));
+
+    let (script, _) = jump_from(&home, &inside, &sibling);
+    assert_eq!(script, format!("cd '{}'\n", sibling.display()));
+
+    let (script, _) = jump_from(&home, &other, &project);
+    assert_eq!(script, format!("cd '{}' && echo entered\n", project.display()));
+
+    fs::remove_dir_all(&home).unwrap();
+  }
+
+  #[test]
+  fn pattern_bindings_apply_after_exact_paths() {
+    let home = fs::canonicalize(scratch_dir("bind-patterns")).unwrap();
+    let api = home.join("work/api");
+    let web = home.join("work/web");
+    let pinned = home.join("work/pinned");
+    let tool = home.join("tools/venv-tool");
+    for dir in [&api, &web, &pinned, &tool] {
+      fs::create_dir_all(dir.join(".venv")).unwrap();
+    }
+    fs::create_dir_all(home.join(".config/ff")).unwrap();
+    fs::write(
+      home.join(".config/ff/config.toml"),
+      "[[bindings]]\nglob = \"~/work/*/\"\nenter = \"echo work\"\n\n\
+       [[bindings]]\nmarker = \".venv/\"\nenter = \"echo venv\"\nleave = \"echo unvenv\"\n",
+    )
+    .unwrap();
+    bind(&home, &pinned, json!("echo pinned"));
+
+    let output = ff(&home).args(["bind", "show"]).arg(&api).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "rule: glob ~/work/*/\ntrust: new\nenter: echo work\n");
+    let output = ff(&home).args(["bind", "show"]).arg(&pinned).output().unwrap();
+    assert_eq!(
+      String::from_utf8_lossy(&output.stdout),
+      format!("rule: path {}\ntrust: new\nenter: echo pinned\n", pinned.display())
+    );
+
+    let (script, output) = jump(&home, &web);
+    assert!(!script.contains("echo work"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("by glob ~/work/*/"));
+
+    let status = ff(&home).args(["bind", "allow"]).arg(&api).status().unwrap();
+    assert!(status.success());
+    let status = ff(&home).args(["bind", "allow"]).arg(&tool).status().unwrap();
+    assert!(status.success());
+
+    let (script, _) = jump(&home, &web);
+    assert_eq!(script, format!("cd '{}' && echo work\n", web.display()));
+    let (script, _) = jump(&home, &pinned);
+    assert!(!script.contains("echo"));
+    let (script, _) = jump_from(&home, &tool, &api);
+    assert_eq!(script, format!("echo unvenv\ncd '{}' && echo work\n", api.display()));
+
+    fs::write(home.join(".config/ff/config.toml"), "[[bindings]]\nenter = \"echo\"\n").unwrap();
+    let output = ff(&home).args(["bind", "show"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("[[bindings]] needs a glob or a marker"));
+
+    fs::remove_dir_all(&home).unwrap();
+  }
+
+  #[test]
+  fn inherited_bindings_apply_to_subdirectories() {
+    let home = fs::canonicalize(scratch_dir("bind-inherit")).unwrap();
+    let api = home.join("work/api");
+    let src = api.join("src");
+    let handlers = src.join("handlers");
+    let legacy = api.join("legacy");
+    let legacy_sub = legacy.join("sub");
+    for dir in [&handlers, &legacy_sub] {
+      fs::create_dir_all(dir).unwrap();
+    }
+    bind_all(
+      &home,
+      &[
+        (&api, json!({ "enter": "echo api", "leave": "echo bye api", "inherit": true })),
+        (&handlers, json!({ "enter": "echo handlers", "extend": true })),
+        (&legacy, json!({ "enter": "echo legacy", "leave": "echo bye legacy", "inherit": true })),
+      ],
+    );
+    for dir in [&handlers, &legacy] {
+      let status = ff(&home).args(["bind", "allow"]).arg(dir).status().unwrap();
+      assert!(status.success());
+    }
+
+    let output = ff(&home).args(["bind", "show"]).arg(&handlers).output().unwrap();
+    assert_eq!(
+      String::from_utf8_lossy(&output.stdout),
+      format!(
+        "rule: path {}\ntrust: allowed\nenter: echo api\nleave: echo bye api\ninherit: yes\n\n\
+         rule: path {}\ntrust: allowed\nenter: echo handlers\nextend: yes\n",
+        api.display(),
+        handlers.display()
+      )
+    );
+
+    let (script, _) = jump(&home, &handlers);
+    assert_eq!(script, format!("cd '{}' && echo api && echo handlers\n", handlers.display()));
+
+    let (script, _) = jump_from(&home, &src, &handlers);
+    assert_eq!(script, format!("cd '{}' && echo handlers\n", handlers.display()));
+
+    let (script, _) = jump_from(&home, &handlers, &legacy_sub);
+    assert_eq!(script, format!("echo bye api\ncd '{}' && echo legacy\n", legacy_sub.display()));
+
+    let (script, _) = jump_from(&home, &legacy_sub, &home);
+    assert_eq!(script, format!("echo bye legacy\ncd '{}'\n", home.display()));
+
+    fs::remove_dir_all(&home).unwrap();
+  }
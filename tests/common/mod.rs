@@ -0,0 +1,39 @@
+#![allow(dead_code)]
+
+  use std::fs;
+  use std::path::{Path, PathBuf};
+  use std::process::Command;
+
+  pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ff-test-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::canonicalize(dir).unwrap()
+  }
+
+  pub fn ff(home: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_ff"));
+    command
+      .env("HOME", home)
+      .env_remove("FF_HOME")
+      .env_remove("XDG_CONFIG_HOME")
+      .env_remove("XDG_DATA_HOME")
+      .env_remove("XDG_STATE_HOME")
+      .env_remove("FF_EXCLUDE_DIRS")
+      .env_remove("FF_SHELL")
+      .env_remove("FF_OUT");
+    command
+  }
+
+  pub fn history_file(home: &Path) -> PathBuf {
+    home.join(".local/share/ff/history")
+  }
+
+  pub fn visits(history: &str, path: &Path) -> Option<u64> {
+    let prefix = format!("{}|", path.display());
+    history
+      .lines()
+      .find_map(|line| line.strip_prefix(&prefix))
+      .and_then(|rest| rest.split('|').next())
+      .and_then(|visits| visits.parse().ok())
+  }
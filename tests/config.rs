@@ -0,0 +1,119 @@
+use std::fs;
+
+  mod common;
+  use common::{ff, scratch_dir};
+
+  #[test]
+  fn dotfiles_are_migrated_to_xdg_locations() {
+    let home = scratch_dir("config-migration");
+    let target = home.join("project");
+    fs::create_dir(&target).unwrap();
+    fs::write(home.join(".ff_config"), format!("{}|1700000000\n", target.display())).unwrap();
+    fs::write(home.join(".ff_scripts"), "{\"/tmp\": \"echo hi\"}").unwrap();
+
+    let output = ff(&home).args(["query", "project"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), format!("{}\n", target.display()));
+
+    let data_dir = home.join(".local/share/ff");
+    assert!(data_dir.join("history").exists());
+    assert_eq!(fs::read_to_string(data_dir.join("scripts.json")).unwrap(), "{\"/tmp\": \"echo hi\"}");
+    assert!(!home.join(".config/ff/config.toml").exists());
+    for dotfile in [".ff_config", ".ff_scripts"] {
+      assert!(!home.join(dotfile).exists(), "{} was left behind", dotfile);
+    }
+
+    fs::remove_dir_all(&home).unwrap();
+  }
+
+  #[test]
+  fn ff_home_holds_config_and_data() {
+    let home = scratch_dir("config-ff-home");
+    let ff_home = home.join("ff-home");
+    let kept = home.join("kept");
+    let skipped = home.join("scratch");
+    fs::create_dir_all(&ff_home).unwrap();
+    fs::create_dir(&kept).unwrap();
+    fs::create_dir(&skipped).unwrap();
+    fs::write(home.join(".ff_config"), "").unwrap();
+    fs::write(ff_home.join("config.toml"), "[exclude]\ndirs = [\"~/scratch\"]\n").unwrap();
+
+    let status = ff(&home).env("FF_HOME", &ff_home).arg("add").arg(&kept).arg(&skipped).status().unwrap();
+    assert!(status.success());
+    let status = ff(&home)
+      .env("FF_HOME", &ff_home)
+      .env("FF_HOOK", "0")
+      .env("FF_OUT", home.join("handoff"))
+      .arg(&skipped)
+      .status()
+      .unwrap();
+    assert!(status.success());
+
+    let history = fs::read_to_string(ff_home.join("history")).unwrap();
+    assert!(history.contains(&format!("{}|", kept.display())));
+    assert!(!history.contains(&format!("{}|", skipped.display())));
+    assert!(home.join(".ff_config").exists());
+    assert!(!home.join(".local").exists());
+
+    fs::remove_dir_all(&home).unwrap();
+  }
+
+  #[test]
+  fn config_flag_sets_ranking() {
+    let home = scratch_dir("config-flag");
+    let target = home.join("project");
+    fs::create_dir(&target).unwrap();
+    let config_file = home.join("custom.toml");
+    fs::write(&config_file, "[ranking]\nhour = 1000\n").unwrap();
+
+    let status = ff(&home).arg("add").arg(&target).status().unwrap();
+    assert!(status.success());
+
+    let output = ff(&home)
+      .arg("--config")
+      .arg(&config_file)
+      .args(["query", "--score", "project"])
+      .output()
+      .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), format!("{:>8} {}\n", 1000, target.display()));
+
+    fs::write(&config_file, "[ranking]\nhour = \"lots\"\n").unwrap();
+    let output = ff(&home).arg("--config").arg(&config_file).args(["query", "project"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("[ranking] hour must be a non-negative integer"));
+
+    fs::remove_dir_all(&home).unwrap();
+  }
+
+  #[test]
+  fn conflicting_key_bindings_are_rejected() {
+    let home = scratch_dir("config-keys");
+    let config_file = home.join("keys.toml");
+
+    fs::write(&config_file, "[keys.normal]\nparent = \"h\"\n").unwrap();
+    let output = ff(&home).arg("--config").arg(&config_file).args(["query", "x"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    fs::write(&config_file, "[keys.normal]\npreview = \"g\"\ntoggle_files = \"g p\"\n").unwrap();
+    let output = ff(&home).arg("--config").arg(&config_file).args(["query", "x"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("'g p' for toggle_files conflicts with a binding for preview"));
+
+    fs::write(&config_file, "[keys.normal]\npreview = \"shift-x\"\ntoggle_files = \"X\"\n").unwrap();
+    let output = ff(&home).arg("--config").arg(&config_file).args(["query", "x"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("conflicts with a binding for"));
+
+    fs::write(&config_file, "[keys.normal]\npreview = \"shift-1\"\n").unwrap();
+    let output = ff(&home).arg("--config").arg(&config_file).args(["query", "x"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("[keys.normal] invalid key 'shift-1' for preview"));
+
+    fs::write(&config_file, "[keys.search]\nparent = \"h\"\n").unwrap();
+    let output = ff(&home).arg("--config").arg(&config_file).args(["query", "x"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("[keys.search] unknown action 'parent'"));
+
+    fs::remove_dir_all(&home).unwrap();
+  }
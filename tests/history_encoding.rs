@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+  use std::ffi::OsString;
+  use std::fs;
+  use std::os::unix::ffi::{OsStrExt, OsStringExt};
+  use std::path::{Path, PathBuf};
+
+  mod common;
+  use common::{ff, history_file, scratch_dir};
+
+  const CASES: usize = 200;
+
+  struct XorShift(u64);
+
+  impl XorShift {
+    fn next(&mut self) -> u64 {
+      self.0 ^= self.0 << 13;
+      self.0 ^= self.0 >> 7;
+      self.0 ^= self.0 << 17;
+      self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+      (self.next() % bound as u64) as usize
+    }
+  }
+
+  fn random_name(rng: &mut XorShift) -> OsString {
+    const PIECES: &[&[u8]] = &[
+      b"|", b"\n", b"\r", b"%", b"%7C", b"'", b"\"", b" ", b"\\", b",", b"#",
+      b"a", b"Z", b"0", b"-", b"_", b".", "é".as_bytes(), "日本".as_bytes(),
+      &[0x80], &[0xff], &[0xc3], &[0xe2, 0x82],
+    ];
+    let mut name = Vec::new();
+    for _ in 0..1 + rng.below(12) {
+      name.extend_from_slice(PIECES[rng.below(PIECES.len())]);
+    }
+    if name == b"." || name == b".." {
+      name.push(b'x');
+    }
+    OsString::from_vec(name)
+  }
+
+  fn query_all(home: &Path) -> HashSet<PathBuf> {
+    let output = ff(home).args(["query", "--list", "--print0"]).output().unwrap();
+    assert!(output.status.success());
+    output.stdout
+      .split(|&byte| byte == 0)
+      .filter(|path| !path.is_empty())
+      .map(|path| PathBuf::from(OsString::from_vec(path.to_vec())))
+      .collect()
+  }
+
+  #[test]
+  fn arbitrary_directory_names_round_trip() {
+    let home = scratch_dir("encoding-round-trip");
+    let root = home.join("names");
+    fs::create_dir(&root).unwrap();
+
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+    let mut created = Vec::new();
+    for _ in 0..CASES {
+      let dir = root.join(random_name(&mut rng));
+      if fs::create_dir(&dir).is_ok() {
+        created.push(dir);
+      }
+    }
+    assert!(created.len() > CASES / 2);
+
+    for batch in created.chunks(50) {
+      let status = ff(&home).arg("add").arg("--").args(batch).status().unwrap();
+      assert!(status.success());
+    }
+
+    let recorded = query_all(&home);
+    for dir in &created {
+      assert!(recorded.contains(dir), "lost {:?}", dir.as_os_str().as_bytes());
+    }
+
+    let status = ff(&home).arg("add").arg(&created[0]).status().unwrap();
+    assert!(status.success());
+    assert_eq!(query_all(&home), recorded);
+
+    fs::remove_dir_all(&home).unwrap();
+  }
+
+  #[test]
+  fn legacy_history_is_migrated() {
+    let home = scratch_dir("encoding-migration");
+    let old = home.join("old");
+    fs::create_dir(&old).unwrap();
+    fs::write(home.join(".ff_config"), format!("{}|1700000000\n", old.display())).unwrap();
+
+    let status = ff(&home).arg("add").arg(&home).status().unwrap();
+    assert!(status.success());
+
+    let history = fs::read_to_string(history_file(&home)).unwrap();
+    assert!(history.starts_with("# ff history v2\n"));
+    assert!(history.contains(&format!("{}|1|1700000000\n", old.display())));
+
+    fs::remove_dir_all(&home).unwrap();
+  }
@@ -0,0 +1,85 @@
+use std::fs;
+  use std::io::Write;
+  use std::path::Path;
+  use std::process::{Command, Stdio};
+
+  mod common;
+  use common::{ff, history_file, scratch_dir, visits};
+
+  fn interactive_bash(home: &Path, script: &str) {
+    let bin_dir = Path::new(env!("CARGO_BIN_EXE_ff")).parent().unwrap();
+    let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap_or_default());
+    let output = Command::new("bash")
+      .args(["--norc", "--noprofile", "-i"])
+      .env("HOME", home)
+      .env("PATH", path)
+      .env_remove("FF_HOME")
+      .env_remove("XDG_CONFIG_HOME")
+      .env_remove("XDG_DATA_HOME")
+      .env_remove("XDG_STATE_HOME")
+      .env_remove("FF_EXCLUDE_DIRS")
+      .env_remove("PROMPT_COMMAND")
+      .current_dir(home)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()
+      .and_then(|mut child| {
+        child.stdin.take().unwrap().write_all(script.as_bytes())?;
+        child.wait_with_output()
+      })
+      .unwrap();
+    assert!(output.status.success(), "bash failed: {}", String::from_utf8_lossy(&output.stderr));
+  }
+
+  #[test]
+  fn bash_hook_records_plain_cd() {
+    let home = scratch_dir("init-hook");
+    let visited = home.join("visited");
+    let jumped = home.join("jumped");
+    fs::create_dir(&visited).unwrap();
+    fs::create_dir(&jumped).unwrap();
+
+    let script = format!(
+      "eval \"$(command ff init bash)\"\ncd '{}'\ncd ..\nff '{}'\n[ \"$PWD\" = '{}' ] || exit 3\nexit\n",
+      visited.display(),
+      jumped.display(),
+      jumped.display()
+    );
+    interactive_bash(&home, &script);
+
+    let history = fs::read_to_string(history_file(&home)).unwrap();
+    assert_eq!(visits(&history, &visited), Some(1));
+    assert_eq!(visits(&history, &jumped), Some(1));
+
+    fs::remove_dir_all(&home).unwrap();
+  }
+
+  #[test]
+  fn no_hook_leaves_recording_to_ff() {
+    let home = scratch_dir("init-no-hook");
+    let visited = home.join("visited");
+    let jumped = home.join("jumped");
+    fs::create_dir(&visited).unwrap();
+    fs::create_dir(&jumped).unwrap();
+
+    let output = ff(&home).args(["init", "bash", "--no-hook"]).output().unwrap();
+    assert!(output.status.success());
+    let init = String::from_utf8(output.stdout).unwrap();
+    assert!(init.contains("FF_HOOK=0"));
+    assert!(!init.contains("PROMPT_COMMAND"));
+
+    let script = format!(
+      "eval \"$(command ff init bash --no-hook)\"\ncd '{}'\ncd ..\nff '{}'\n[ \"$PWD\" = '{}' ] || exit 3\nexit\n",
+      visited.display(),
+      jumped.display(),
+      jumped.display()
+    );
+    interactive_bash(&home, &script);
+
+    let history = fs::read_to_string(history_file(&home)).unwrap();
+    assert_eq!(visits(&history, &visited), None);
+    assert_eq!(visits(&history, &jumped), Some(1));
+
+    fs::remove_dir_all(&home).unwrap();
+  }
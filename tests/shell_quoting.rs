@@ -0,0 +1,110 @@
+use std::fs;
+  use std::os::unix::ffi::OsStrExt;
+  use std::path::{Path, PathBuf};
+  use std::process::{Command, Stdio};
+
+  mod common;
+  use common::{ff, scratch_dir};
+
+  const HOSTILE_NAMES: &[&str] = &[
+    "it's",
+    "x'; touch pwned; '",
+    "$(touch pwned)",
+    "`touch pwned`",
+    "a\"b",
+    "semi;colon && touch pwned",
+    "new\nline",
+    "tab\there",
+    "back\\slash",
+    "*?[glob]",
+    "~tilde",
+    "${HOME}",
+    "-dash",
+  ];
+
+  fn hostile_dirs(home: &Path) -> Vec<PathBuf> {
+    let dirs: Vec<PathBuf> = HOSTILE_NAMES.iter().map(|name| home.join(name)).collect();
+    for dir in &dirs {
+      fs::create_dir(dir).unwrap();
+    }
+    dirs
+  }
+
+  fn installed(shell: &str) -> bool {
+    let installed = Command::new(shell).args(["-c", "exit 0"]).status().is_ok_and(|status| status.success());
+    if !installed {
+      eprintln!("skipping {}: not installed", shell);
+    }
+    installed
+  }
+
+  fn handoff(home: &Path, shell: Option<&str>, dir: &Path) -> Vec<u8> {
+    let handoff = home.join("handoff");
+    let mut command = ff(home);
+    if let Some(shell) = shell {
+      command.env("FF_SHELL", shell);
+    }
+    let status = command.env("FF_HOOK", "0").env("FF_OUT", &handoff).arg(dir).status().unwrap();
+    assert!(status.success());
+    fs::read(&handoff).unwrap()
+  }
+
+  fn source_in(shell: &str, home: &Path, script: &[u8]) -> Vec<u8> {
+    let script_file = home.join("script");
+    fs::write(&script_file, script).unwrap();
+    let mut command = Command::new(shell);
+    if shell == "fish" {
+      command.arg("-c").arg(format!("source '{}'; and printf %s $PWD", script_file.display()));
+    } else {
+      command.arg("-c").arg(". \"$1\" && printf %s \"$PWD\"").arg("ff-test").arg(&script_file);
+    }
+    let output = command.current_dir(home).output().unwrap();
+    assert!(output.status.success(), "{} failed on {:?}", shell, String::from_utf8_lossy(script));
+    output.stdout
+  }
+
+  #[test]
+  fn hostile_names_are_quoted_in_the_handoff_script() {
+    let home = scratch_dir("quoting-handoff");
+    let dirs = hostile_dirs(&home);
+
+    for (ff_shell, shell) in [(Some("bash"), "bash"), (Some("zsh"), "zsh"), (Some("fish"), "fish"), (None, "sh")] {
+      if !installed(shell) {
+        continue;
+      }
+      for dir in &dirs {
+        let script = handoff(&home, ff_shell, dir);
+        assert_eq!(source_in(shell, &home, &script), dir.as_os_str().as_bytes(), "{:?} in {}", dir, shell);
+      }
+    }
+    assert!(!home.join("pwned").exists());
+    for dir in &dirs {
+      assert!(!dir.join("pwned").exists());
+    }
+
+    fs::remove_dir_all(&home).unwrap();
+  }
+
+  #[test]
+  fn shell_fallback_enters_hostile_directories() {
+    let home = scratch_dir("quoting-fallback");
+    let marker = home.join("marker");
+    let dir = home.join("x'; touch pwned; '$(touch pwned)");
+    fs::create_dir(&dir).unwrap();
+
+    let data_dir = home.join(".local/share/ff");
+    fs::create_dir_all(&data_dir).unwrap();
+    let command = format!("printf %s \"$PWD\" > '{}'", marker.display());
+    let scripts = serde_json::json!({ dir.to_str().unwrap(): command });
+    fs::write(data_dir.join("scripts.json"), scripts.to_string()).unwrap();
+    let status = ff(&home).args(["bind", "allow"]).arg(&dir).status().unwrap();
+    assert!(status.success());
+
+    let status = ff(&home).env("SHELL", "/bin/sh").arg(&dir).stdin(Stdio::null()).status().unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read_to_string(&marker).unwrap(), dir.to_str().unwrap());
+    assert!(!home.join("pwned").exists());
+    assert!(!dir.join("pwned").exists());
+
+    fs::remove_dir_all(&home).unwrap();
+  }
@@ -0,0 +1,93 @@
+use std::fs;
+  use std::path::{Path, PathBuf};
+  use std::thread;
+
+  mod common;
+  use common::{ff, history_file, scratch_dir, visits};
+
+  const PROCESSES: usize = 8;
+  const VISITS: usize = 25;
+
+  #[test]
+  fn concurrent_adds_are_all_recorded() {
+    let home = scratch_dir("concurrent-adds");
+    let targets: Vec<PathBuf> = (0..PROCESSES)
+      .map(|index| {
+        let target = home.join(format!("dir{}", index));
+        fs::create_dir(&target).unwrap();
+        target
+      })
+      .collect();
+
+    let workers: Vec<_> = targets
+      .iter()
+      .cloned()
+      .map(|target| {
+        let home = home.clone();
+        thread::spawn(move || {
+          for _ in 0..VISITS {
+            let status = ff(&home).arg("add").arg(&target).status().unwrap();
+            assert!(status.success());
+          }
+        })
+      })
+      .collect();
+    for worker in workers {
+      worker.join().unwrap();
+    }
+
+    let history = fs::read_to_string(history_file(&home)).unwrap();
+    for target in &targets {
+      assert_eq!(visits(&history, target), Some(VISITS as u64), "{}", target.display());
+    }
+    assert_eq!(visits(&history, &home), None);
+    assert_eq!(visits(&history, Path::new("/")), None);
+
+    let leftovers: Vec<_> = fs::read_dir(history_file(&home).parent().unwrap())
+      .unwrap()
+      .filter_map(Result::ok)
+      .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+      .collect();
+    assert!(leftovers.is_empty());
+
+    fs::remove_dir_all(&home).unwrap();
+  }
+
+  #[test]
+  fn legacy_history_is_upgraded_in_place() {
+    let home = scratch_dir("legacy-upgrade");
+    let target = home.join("project");
+    fs::create_dir(&target).unwrap();
+    fs::write(home.join(".ff_config"), format!("{}|1700000000\n", target.display())).unwrap();
+
+    let status = ff(&home).arg("add").arg(&target).status().unwrap();
+    assert!(status.success());
+
+    let history = fs::read_to_string(history_file(&home)).unwrap();
+    assert_eq!(visits(&history, &target), Some(2));
+    assert!(!home.join(".ff_config").exists());
+
+    fs::remove_dir_all(&home).unwrap();
+  }
+
+  #[test]
+  fn relative_paths_are_recorded_once() {
+    let home = scratch_dir("relative-add");
+    let parent = home.join("a");
+    let child = parent.join("b");
+    fs::create_dir_all(&child).unwrap();
+
+    for path in ["..", ".", "../b/.."] {
+      let status = ff(&home).current_dir(&child).arg("add").arg(path).status().unwrap();
+      assert!(status.success());
+    }
+    let status = ff(&home).arg("add").arg(&parent).arg(home.join("missing/..")).status().unwrap();
+    assert!(status.success());
+
+    let history = fs::read_to_string(history_file(&home)).unwrap();
+    assert_eq!(visits(&history, &parent), Some(3));
+    assert_eq!(visits(&history, &child), Some(1));
+    assert_eq!(history.lines().filter(|line| line.contains("..") || line.contains("/.|")).count(), 0);
+
+    fs::remove_dir_all(&home).unwrap();
+  }